@@ -1,16 +1,48 @@
-use std::path::PathBuf;
+use stringexts::{DelimitedWriter, Input, Radix, TextWriter};
 
 pub fn main() -> stringexts::Result<()> {
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() == 1 {
-        println!("please give filename to extract strings!");
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    let mut format = String::from("text");
+    if let Some(idx) = args.iter().position(|arg| arg == "--format") {
+        if idx + 1 < args.len() {
+            format = args.remove(idx + 1);
+        }
+        args.remove(idx);
+    }
+    if args.is_empty() {
+        println!("please give filename to extract strings, or `-` for stdin!");
+        println!("usage: stringsext_cli [--format text|json|csv|tsv] FILE");
         return Ok(());
     }
-    let file = args[1].to_owned();
 
-    let mut str_scan =
-        stringexts::StringsScanner::new(None, &[], None, false, None, None, None, None)?;
-    let res = str_scan.run(vec![PathBuf::from(file)])?;
-    println!("{:?}", res);
+    let str_scan = stringexts::StringsScanner::new(None, &[], None, false, None, None, None, None)?;
+    let res = str_scan.run([Input::from_arg(&args[0])])?;
+    match format.as_str() {
+        #[cfg(feature = "serde")]
+        "json" => {
+            let mut out = stringexts::JsonLinesWriter::new(std::io::stdout().lock());
+            for finding in &res.findings {
+                out.write(finding)?;
+            }
+        }
+        "csv" | "tsv" => {
+            let out = std::io::stdout().lock();
+            let mut out = if format == "csv" {
+                DelimitedWriter::csv(out, Radix::X)
+            } else {
+                DelimitedWriter::tsv(out, Radix::X)
+            };
+            for finding in &res.findings {
+                out.write(finding)?;
+            }
+        }
+        "text" => {
+            let mut out = TextWriter::new(std::io::stdout().lock()).radix(Some(Radix::X));
+            for finding in &res.findings {
+                out.write(finding)?;
+            }
+        }
+        _ => eprintln!("unsupported output format `{}`", format),
+    }
     Ok(())
 }
@@ -0,0 +1,229 @@
+//! Scanning of `tokio::io::AsyncRead` inputs, enabled with the `async`
+//! feature.
+
+use crate::{
+    error::Error, finding::OwnedFinding, group_filter::GroupFilter, input::InputLabel,
+    mission::Missions, scanner::ScannerStates, scheduler,
+};
+use futures_core::Stream;
+use std::{
+    collections::VecDeque,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// A `Stream` of the findings in an `AsyncRead` input, created by
+/// `StringsScanner::scan_async()`.
+///
+/// The input is read chunk by chunk. Every chunk is scanned by all missions
+/// on the polling task before the next chunk is read, so no extra thread is
+/// needed per input. Findings are yielded in the same order as
+/// `StringsScanner::run()` would return them.
+pub struct AsyncScan<R> {
+    reader: R,
+    input_label: Arc<InputLabel>,
+    scanner_states: ScannerStates,
+    group_filter: Option<GroupFilter>,
+    input_buffer: Box<[u8]>,
+    findings: VecDeque<OwnedFinding>,
+    /// A read error, yielded after the findings held back before it.
+    error: Option<Error>,
+    input_ended: bool,
+}
+
+impl<R> AsyncScan<R> {
+    pub(crate) fn new(reader: R, missions: &Missions, chunk_size: usize) -> Self {
+        Self {
+            reader,
+            input_label: Arc::new(InputLabel::Label("#1".to_string())),
+            scanner_states: ScannerStates::new(missions, 0),
+            group_filter: GroupFilter::new(missions),
+            input_buffer: vec![0u8; chunk_size].into_boxed_slice(),
+            findings: VecDeque::new(),
+            error: None,
+            input_ended: false,
+        }
+    }
+
+    /// Reports the findings with `label` instead of `#1`.
+    pub fn labeled<S: Into<String>>(mut self, label: S) -> Self {
+        self.input_label = Arc::new(InputLabel::Label(label.into()));
+        self
+    }
+
+    /// Scans the first `len` bytes of `input_buffer` with all missions and
+    /// queues the merged findings.
+    fn scan_chunk(&mut self, len: usize) {
+        let mut findings = scheduler::scan_chunk(
+            &self.scanner_states,
+            None,
+            Some(1),
+            &self.input_label,
+            &self.input_buffer[..len],
+            self.input_ended,
+        );
+        if let Some(group_filter) = self.group_filter.as_mut() {
+            findings = group_filter.filter(findings, self.input_ended);
+        }
+        self.findings.extend(findings);
+    }
+}
+
+impl<R: AsyncRead + Unpin> Stream for AsyncScan<R> {
+    type Item = crate::Result<OwnedFinding>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        loop {
+            if let Some(finding) = this.findings.pop_front() {
+                return Poll::Ready(Some(Ok(finding)));
+            }
+            if let Some(error) = this.error.take() {
+                return Poll::Ready(Some(Err(error)));
+            }
+            if this.input_ended {
+                return Poll::Ready(None);
+            }
+            let mut buf = ReadBuf::new(&mut this.input_buffer);
+            match Pin::new(&mut this.reader).poll_read(cx, &mut buf) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Err(e)) => {
+                    // Like `scheduler::run_job()`, hand out the held back
+                    // findings before the error.
+                    this.input_ended = true;
+                    if let Some(group_filter) = this.group_filter.as_mut() {
+                        this.findings.extend(group_filter.filter(Vec::new(), true));
+                    }
+                    let input_label = (*this.input_label).clone();
+                    this.error = Some(Error::Input(input_label, e));
+                }
+                Poll::Ready(Ok(())) => {
+                    let len = buf.filled().len();
+                    // An empty read marks the end of the input. The last
+                    // scan run flushes strings touching the end.
+                    this.input_ended = len == 0;
+                    this.scan_chunk(len);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MissionConfig, OwnedFinding, StringsScanner};
+    use encoding_rs::{UTF_16LE, UTF_8};
+    use futures_core::Stream;
+    use regex::Regex;
+    use std::{
+        future::poll_fn,
+        io,
+        pin::Pin,
+        task::{Context, Poll},
+    };
+    use tokio::io::{AsyncRead, ReadBuf};
+
+    fn scanner(regex: Option<&str>) -> StringsScanner {
+        let regex = regex.map(|regex| Regex::new(regex).unwrap());
+        StringsScanner::builder()
+            .mission(
+                MissionConfig::new(UTF_8)
+                    .output_line_len(10)
+                    .regex(regex.clone()),
+            )
+            .mission(
+                MissionConfig::new(UTF_16LE)
+                    .output_line_len(10)
+                    .regex(regex),
+            )
+            .chunk_size(16)
+            .build()
+            .unwrap()
+    }
+
+    /// Collects the stream, stopping after the first error.
+    async fn collect<S>(mut stream: S) -> (Vec<OwnedFinding>, Option<crate::error::Error>)
+    where
+        S: Stream<Item = crate::Result<OwnedFinding>> + Unpin,
+    {
+        let mut findings = Vec::new();
+        while let Some(item) = poll_fn(|cx| Pin::new(&mut stream).poll_next(cx)).await {
+            match item {
+                Ok(finding) => findings.push(finding),
+                Err(e) => return (findings, Some(e)),
+            }
+        }
+        (findings, None)
+    }
+
+    fn rows(findings: &[OwnedFinding]) -> Vec<(u64, usize, String, bool)> {
+        findings
+            .iter()
+            .map(|finding| {
+                (
+                    finding.position,
+                    finding.mission.mission_id,
+                    finding.s.clone(),
+                    finding.s_completes_previous_s,
+                )
+            })
+            .collect()
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn yields_the_same_findings_as_scan_bytes() {
+        let mut bytes = b"\0\0a string spanning several chunks\0\0short\0".to_vec();
+        bytes.extend(
+            "UTF-16 text across chunks"
+                .encode_utf16()
+                .flat_map(u16::to_le_bytes),
+        );
+        bytes.extend_from_slice(b"\0\0the end");
+        for regex in [None, Some("s")] {
+            let scanner = scanner(regex);
+            let expected = scanner.scan_bytes(&bytes).unwrap().findings;
+            assert!(expected
+                .iter()
+                .any(|finding| finding.s_completes_previous_s));
+            let (findings, error) = collect(scanner.scan_async(&bytes[..])).await;
+            assert!(error.is_none());
+            assert_eq!(rows(&findings), rows(&expected));
+        }
+    }
+
+    /// Hands out `bytes`, then fails.
+    struct FailingReader<'a> {
+        bytes: &'a [u8],
+    }
+
+    impl AsyncRead for FailingReader<'_> {
+        fn poll_read(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+            buf: &mut ReadBuf<'_>,
+        ) -> Poll<io::Result<()>> {
+            if self.bytes.is_empty() {
+                return Poll::Ready(Err(io::Error::other("device gone")));
+            }
+            let len = self.bytes.len().min(buf.remaining());
+            buf.put_slice(&self.bytes[..len]);
+            self.bytes = &self.bytes[len..];
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test(flavor = "current_thread")]
+    async fn held_back_findings_come_before_a_read_error() {
+        // The pieces of `touching the end` are held back by the group filter
+        // until the string is complete, which the error decides.
+        let reader = FailingReader {
+            bytes: b"\0first string\0\0touching the end",
+        };
+        let (findings, error) = collect(scanner(Some("t")).scan_async(reader)).await;
+        let strings: Vec<_> = findings.iter().map(|finding| finding.s.as_str()).collect();
+        assert_eq!(strings.concat(), "first stringtouching the end");
+        assert!(matches!(error, Some(crate::error::Error::Input(..))));
+    }
+}
@@ -0,0 +1,201 @@
+//! CSV and TSV output of findings.
+//!
+//! Both formats quote fields like RFC 4180: a field containing the
+//! delimiter, a quote, a carriage return or a line feed is enclosed in
+//! quotes, and quotes in it are doubled. Findings can contain all of these
+//! when the ASCII filter lets whitespace and control characters through.
+//! Spreadsheet applications read both formats this way.
+
+use crate::{finding::OwnedFinding, options::Radix};
+use std::io::Write;
+
+const HEADER: [&str; 5] = ["file", "offset", "precision", "encoding", "string"];
+
+const HEADER_WITH_LANGUAGE: [&str; 7] = [
+    "file",
+    "offset",
+    "precision",
+    "encoding",
+    "language",
+    "confidence",
+    "string",
+];
+
+/// Writes findings as delimiter separated records, one per line, after a
+/// header line.
+pub struct DelimitedWriter<W: Write> {
+    out: W,
+    delimiter: u8,
+    radix: Radix,
+    language_column: bool,
+    header_written: bool,
+}
+
+impl<W: Write> DelimitedWriter<W> {
+    /// Comma separated values. Offsets are written in `radix`.
+    pub fn csv(out: W, radix: Radix) -> Self {
+        Self::new(out, b',', radix)
+    }
+
+    /// Tab separated values. Offsets are written in `radix`.
+    pub fn tsv(out: W, radix: Radix) -> Self {
+        Self::new(out, b'\t', radix)
+    }
+
+    fn new(out: W, delimiter: u8, radix: Radix) -> Self {
+        Self {
+            out,
+            delimiter,
+            radix,
+            language_column: false,
+            header_written: false,
+        }
+    }
+
+    /// Adds the columns `language` and `confidence` before `string`, see
+    /// `OwnedFinding::language`. Both are empty for findings without a
+    /// language.
+    pub fn language_column(mut self, language_column: bool) -> Self {
+        self.language_column = language_column;
+        self
+    }
+
+    pub fn write(&mut self, finding: &OwnedFinding) -> crate::Result<()> {
+        if !self.header_written {
+            if self.language_column {
+                self.write_record(&HEADER_WITH_LANGUAGE)?;
+            } else {
+                self.write_record(&HEADER)?;
+            }
+            self.header_written = true;
+        }
+        let file = finding.input_label.to_string();
+        let offset = self.radix.format(finding.position);
+        let precision = finding.position_precision.marker().to_string();
+        if !self.language_column {
+            return self.write_record(&[
+                &file,
+                &offset,
+                &precision,
+                finding.encoding_name(),
+                &finding.s,
+            ]);
+        }
+        let (language, confidence) = match finding.language {
+            Some(guess) => (guess.language, format!("{:.2}", guess.confidence)),
+            None => ("", String::new()),
+        };
+        self.write_record(&[
+            &file,
+            &offset,
+            &precision,
+            finding.encoding_name(),
+            language,
+            &confidence,
+            &finding.s,
+        ])
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+
+    fn write_record(&mut self, fields: &[&str]) -> crate::Result<()> {
+        for (idx, field) in fields.iter().enumerate() {
+            if idx > 0 {
+                self.out.write_all(&[self.delimiter])?;
+            }
+            self.write_field(field)?;
+        }
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn write_field(&mut self, field: &str) -> crate::Result<()> {
+        let needs_quotes = field
+            .bytes()
+            .any(|b| b == self.delimiter || b == b'"' || b == b'\r' || b == b'\n');
+        if !needs_quotes {
+            self.out.write_all(field.as_bytes())?;
+            return Ok(());
+        }
+        self.out.write_all(b"\"")?;
+        for (idx, part) in field.split('"').enumerate() {
+            if idx > 0 {
+                self.out.write_all(b"\"\"")?;
+            }
+            self.out.write_all(part.as_bytes())?;
+        }
+        self.out.write_all(b"\"")?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MissionConfig, StringsScanner, AF_ALL};
+
+    fn csv(mission: MissionConfig) -> String {
+        let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+        let result = scanner
+            .scan_bytes(b"\0the cat and the dog\0\0x, \"y\"\0")
+            .unwrap();
+        let mut out = DelimitedWriter::csv(Vec::new(), Radix::D).language_column(true);
+        for finding in &result.findings {
+            out.write(finding).unwrap();
+        }
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    #[test]
+    fn language_columns_are_empty_without_a_language() {
+        let mission = MissionConfig::ascii().chars_min(5).detect_language(true);
+        assert_eq!(
+            csv(mission),
+            "file,offset,precision,encoding,language,confidence,string\n\
+             #1,0, ,ascii,en,0.89,the cat and the dog\n\
+             #1,0,>,ascii,,,\"x, \"\"y\"\"\"\n"
+        );
+    }
+
+    /// Writes the findings of an `AF_ALL` scan of `bytes` to `out`.
+    fn write_all(mut out: DelimitedWriter<Vec<u8>>, bytes: &[u8]) -> String {
+        let mission = MissionConfig::ascii().ascii_filter(AF_ALL);
+        let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+        for finding in &scanner.scan_bytes(bytes).unwrap().findings {
+            out.write(finding).unwrap();
+        }
+        String::from_utf8(out.into_inner()).unwrap()
+    }
+
+    const CONTROLS: &[u8] = b"\0tab\there, lf\nand cr\r \"q\"\0";
+
+    #[test]
+    fn csv_quotes_commas_quotes_and_line_breaks() {
+        assert_eq!(
+            write_all(DelimitedWriter::csv(Vec::new(), Radix::D), CONTROLS),
+            "file,offset,precision,encoding,string\n\
+             #1,0, ,ascii,\"tab\there, lf\nand cr\r \"\"q\"\"\"\n"
+        );
+        assert_eq!(
+            write_all(DelimitedWriter::csv(Vec::new(), Radix::D), b"\0tab\tonly\0"),
+            "file,offset,precision,encoding,string\n\
+             #1,0, ,ascii,tab\tonly\n"
+        );
+    }
+
+    #[test]
+    fn tsv_quotes_tabs_quotes_and_line_breaks() {
+        assert_eq!(
+            write_all(DelimitedWriter::tsv(Vec::new(), Radix::D), CONTROLS),
+            "file\toffset\tprecision\tencoding\tstring\n\
+             #1\t0\t \tascii\t\"tab\there, lf\nand cr\r \"\"q\"\"\"\n"
+        );
+        assert_eq!(
+            write_all(DelimitedWriter::tsv(Vec::new(), Radix::D), b"\0a, b\0"),
+            "file\toffset\tprecision\tencoding\tstring\n\
+             #1\t0\t \tascii\ta, b\n"
+        );
+    }
+}
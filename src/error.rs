@@ -1,3 +1,4 @@
+use crate::{input::InputLabel, scheduler::SEGMENT_BOUNDARY_ZEROS};
 use std::{
     io::{BufWriter, IntoInnerError},
     num::ParseIntError,
@@ -8,26 +9,53 @@ use std::{
 pub enum Error {
     #[error("{0}")]
     Io(#[from] std::io::Error),
-    #[error("{0}")]
+    #[error("can not read input `{0}`: {1}")]
+    Input(InputLabel, #[source] std::io::Error),
+    #[error("unknown filter name `{0}`")]
     InvalidFilterName(String),
-    #[error("{0}")]
+    #[error("invalid filter expression `{0}`, column {1}: {2}")]
+    FilterExpression(String, usize, String),
+    #[error("grep char must be ASCII (<= 127), but is {0}")]
     GrepChar(u8),
-    #[error("{0}")]
+    #[error("output line length must be at least {0}, but is {1}")]
     MinimumOutputLineLength(usize, usize),
-    #[error("{0}")]
+    #[error("scanner ({0}): output line length must be at least {1}, but is {2}")]
     ScannerMinimumOutputLineLength(String, usize, usize),
-    #[error("{0}")]
+    #[error("scanner ({0}): grep char must be ASCII (<= 127), but is {1}")]
     ScanerGrepCode(String, u8),
-    #[error("{0}")]
+    #[error("unknown encoding `{0}`")]
     Encoding(String),
-    #[error("{0}")]
+    #[error("unknown script `{0}`")]
+    Script(String),
+    #[error("invalid regular expression: {0}")]
+    Regex(#[from] regex::Error),
+    #[error("invalid keyword list: {0}")]
+    Keywords(String),
+    #[error("can not read keyword list `{0}`: {1}")]
+    KeywordList(String, #[source] std::io::Error),
+    #[error("minimum score must be a number from 0 to 1, but is `{0}`")]
+    MinScore(String),
+    #[error("language field must be `lang` or empty, but is `{0}`")]
+    LanguageField(String),
+    #[error("invalid needle: {0}")]
+    Needle(String),
+    #[error("invalid encoding option `{0}`: expected `enc,min,af,ubf,grep,scripts,score,lang`")]
     TooManyEncodings(String),
+    #[error("chunk size must be at least 1, but is {0}")]
+    ChunkSize(usize),
+    #[error("number of workers must be at least 1, but is {0}")]
+    Workers(usize),
+    #[error("segment length must be at least {SEGMENT_BOUNDARY_ZEROS}, but is {0}")]
+    SegmentLen(usize),
     #[error("{0}")]
     ParseInt(#[from] ParseIntError),
     #[error("{0}")]
     Poison(#[from] PoisonError<BufWriter<Vec<u8>>>),
     #[error("{0}")]
     IntoInner(IntoInnerError<BufWriter<Vec<u8>>>),
+    #[cfg(feature = "serde")]
+    #[error("{0}")]
+    Json(#[from] serde_json::Error),
     #[error("unknown data store error")]
     Unknown,
 }
@@ -0,0 +1,267 @@
+//! Filter expressions for the ASCII and the Unicode block filter.
+//!
+//! ```text
+//! expr := term { ("+" | "-") term }
+//! term := name | "0x" hex-digits | "(" expr ")"
+//! ```
+//!
+//! `a+b` is the union of both masks, `a-b` the mask `a` without `b`. The
+//! operators are left associative: `All-Latin+Greek` is `(All-Latin)+Greek`.
+//! Names are the filter aliases, see `ascii_filter_aliases()` and
+//! `unicode_block_filter_aliases()`, matched case-insensitively. Names
+//! containing `+` or `-`, like `All-Ctrl+Wsp`, are recognized as a whole.
+//! Any other name can be abbreviated to a prefix, which selects the first
+//! alias it matches.
+//!
+//! An unknown name is reported as `Error::InvalidFilterName`, any other
+//! mistake as `Error::FilterExpression` with the column where it starts.
+
+use crate::{
+    error::Error,
+    names::{ascii_filter_aliases, unicode_block_filter_aliases, FilterAlias},
+};
+use std::{
+    num::ParseIntError,
+    ops::{BitAnd, BitOr, Not},
+};
+
+/// Parses an ASCII filter expression, e.g. `All-Ctrl+Wsp` or
+/// `Default-0x8000000`.
+pub fn parse_ascii_filter(expr: &str) -> crate::Result<u128> {
+    let aliases: Vec<_> = ascii_filter_aliases().collect();
+    Parser::new(expr, &aliases, |hex| u128::from_str_radix(hex, 16)).parse()
+}
+
+/// Parses a Unicode block filter expression, e.g. `Latin+Greek+Cyrillic`
+/// or `All-Private`.
+pub fn parse_unicode_block_filter(expr: &str) -> crate::Result<u64> {
+    let aliases: Vec<_> = unicode_block_filter_aliases().collect();
+    Parser::new(expr, &aliases, |hex| u64::from_str_radix(hex, 16)).parse()
+}
+
+/// A recursive descent parser over `expr`. `pos` is the byte position of
+/// the next character to read.
+struct Parser<'a, M> {
+    expr: &'a str,
+    pos: usize,
+    aliases: &'a [FilterAlias<M>],
+    from_hex: fn(&str) -> Result<M, ParseIntError>,
+}
+
+impl<'a, M> Parser<'a, M>
+where
+    M: Copy + BitOr<Output = M> + BitAnd<Output = M> + Not<Output = M>,
+{
+    fn new(
+        expr: &'a str,
+        aliases: &'a [FilterAlias<M>],
+        from_hex: fn(&str) -> Result<M, ParseIntError>,
+    ) -> Self {
+        Self {
+            expr,
+            pos: 0,
+            aliases,
+            from_hex,
+        }
+    }
+
+    fn parse(mut self) -> crate::Result<M> {
+        let mask = self.parse_expr()?;
+        match self.peek() {
+            None => Ok(mask),
+            Some(c) => Err(self.error(self.pos, format!("unexpected `{}`", c))),
+        }
+    }
+
+    fn parse_expr(&mut self) -> crate::Result<M> {
+        let mut mask = self.parse_term()?;
+        loop {
+            match self.peek() {
+                Some('+') => {
+                    self.pos += 1;
+                    mask = mask | self.parse_term()?;
+                }
+                Some('-') => {
+                    self.pos += 1;
+                    mask = mask & !self.parse_term()?;
+                }
+                _ => return Ok(mask),
+            }
+        }
+    }
+
+    fn parse_term(&mut self) -> crate::Result<M> {
+        self.peek();
+        let start = self.pos;
+        let rest = &self.expr[start..];
+        if rest.starts_with('(') {
+            self.pos += 1;
+            let mask = self.parse_expr()?;
+            if self.peek() != Some(')') {
+                return Err(self.error(start, "`(` is not closed".to_string()));
+            }
+            self.pos += 1;
+            return Ok(mask);
+        }
+        if rest.starts_with("0x") || rest.starts_with("0X") {
+            let word = self.word();
+            let hex = word[2..].replace('_', "");
+            return (self.from_hex)(&hex)
+                .map_err(|e| self.error(start, format!("invalid hex mask `{}`: {}", word, e)));
+        }
+        if let Some(alias) = self.full_name(rest) {
+            self.pos += alias.name.len();
+            return Ok(alias.mask);
+        }
+        let word = self.word();
+        if word.is_empty() {
+            return Err(self.error(
+                start,
+                "expected a filter name, a hex mask or `(`".to_string(),
+            ));
+        }
+        self.aliases
+            .iter()
+            .find(|alias| {
+                alias.name.len() >= word.len()
+                    && alias.name[..word.len()].eq_ignore_ascii_case(word)
+            })
+            .map(|alias| alias.mask)
+            .ok_or_else(|| Error::InvalidFilterName(word.to_string()))
+    }
+
+    /// Finds the longest alias name `rest` starts with, that is not
+    /// followed by more name characters.
+    fn full_name(&self, rest: &str) -> Option<&'a FilterAlias<M>> {
+        self.aliases
+            .iter()
+            .filter(|alias| {
+                rest.len() >= alias.name.len()
+                    && rest.is_char_boundary(alias.name.len())
+                    && rest[..alias.name.len()].eq_ignore_ascii_case(alias.name)
+                    && !rest[alias.name.len()..]
+                        .starts_with(|c: char| c.is_ascii_alphanumeric() || c == '_')
+            })
+            .max_by_key(|alias| alias.name.len())
+    }
+
+    /// Reads a name or a number.
+    fn word(&mut self) -> &'a str {
+        let rest = &self.expr[self.pos..];
+        let len = rest
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(rest.len());
+        self.pos += len;
+        &rest[..len]
+    }
+
+    /// Skips whitespace and returns the next character.
+    fn peek(&mut self) -> Option<char> {
+        let rest = &self.expr[self.pos..];
+        self.pos += rest.len() - rest.trim_start().len();
+        self.expr[self.pos..].chars().next()
+    }
+
+    fn error(&self, pos: usize, message: String) -> Error {
+        Error::FilterExpression(self.expr.to_string(), pos + 1, message)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::{
+        AF_ALL, AF_CTRL, AF_DEFAULT, AF_WHITESPACE, UBF_ACCENTS, UBF_ALL, UBF_CYRILLIC, UBF_GREEK,
+        UBF_INVALID, UBF_LATIN,
+    };
+
+    const LATIN: u64 = UBF_LATIN | UBF_ACCENTS;
+    const ALL: u64 = UBF_ALL & !UBF_INVALID;
+
+    #[test]
+    fn unicode_block_filter_expressions() {
+        let cases = [
+            ("Latin", LATIN),
+            ("latin+GREEK", LATIN | UBF_GREEK),
+            // Left associative: `(Greek-Greek)+Greek`, not `Greek-(Greek+Greek)`.
+            ("Greek-Greek+Greek", UBF_GREEK),
+            ("Greek-(Greek+Greek)", 0),
+            ("All-Latin+Greek", ALL & !LATIN),
+            ("All-(Latin+Greek)", ALL & !LATIN & !UBF_GREEK),
+            ("((Latin))", LATIN),
+            (" Latin + ( Greek - Greek ) ", LATIN),
+            ("0xc000", UBF_GREEK),
+            ("0X1f_0000+0xC000", UBF_CYRILLIC | UBF_GREEK),
+            ("Latin-0x1fc", UBF_ACCENTS),
+            // Prefixes select the first alias they match.
+            ("Cy", UBF_CYRILLIC),
+            ("gr+lat", UBF_GREEK | LATIN),
+            // `All-Asian` is a name, not `All` without `Asian`.
+            ("Al", ALL & !crate::mission::UBF_ASIAN),
+            ("All", ALL),
+        ];
+        for (expr, mask) in cases {
+            assert_eq!(parse_unicode_block_filter(expr).unwrap(), mask, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn ascii_filter_expressions() {
+        let cases = [
+            ("All-Ctrl+Wsp", AF_ALL & !AF_CTRL | AF_WHITESPACE),
+            ("all-ctrl+wsp", AF_ALL & !AF_CTRL | AF_WHITESPACE),
+            // The longest name wins, the rest is read as an expression.
+            ("All-Ctrl-Wsp", AF_DEFAULT & !AF_WHITESPACE),
+            ("All-Ctrl+Wsp-Wsp", AF_DEFAULT & !AF_WHITESPACE),
+            ("All-(All-Ctrl+Wsp)", AF_ALL & AF_CTRL & !AF_WHITESPACE),
+            ("Default-0x8000_0000", AF_DEFAULT & !0x8000_0000),
+        ];
+        for (expr, mask) in cases {
+            assert_eq!(parse_ascii_filter(expr).unwrap(), mask, "{}", expr);
+        }
+    }
+
+    #[test]
+    fn syntax_errors_report_their_column() {
+        let cases = [
+            ("", 1, "expected"),
+            ("Latin+", 7, "expected"),
+            ("Latin + ", 9, "expected"),
+            ("Latin+-Greek", 7, "expected"),
+            ("Latin)", 6, "unexpected `)`"),
+            ("Latin Greek", 7, "unexpected `G`"),
+            ("Latin+(Greek", 7, "not closed"),
+            ("((Latin)", 1, "not closed"),
+            ("Latin+0xZZ", 7, "invalid hex mask `0xZZ`"),
+            ("0x", 1, "invalid hex mask"),
+            ("0x1_0000_0000_0000_0000", 1, "invalid hex mask"),
+        ];
+        for (expr, column, message) in cases {
+            match parse_unicode_block_filter(expr) {
+                Err(Error::FilterExpression(e, c, m)) => {
+                    assert_eq!(e, expr);
+                    assert_eq!(c, column, "{}", expr);
+                    assert!(m.contains(message), "{}: {}", expr, m);
+                }
+                r => panic!("{}: {:?}", expr, r),
+            }
+        }
+    }
+
+    #[test]
+    fn unknown_names_are_invalid_filter_names() {
+        for (expr, name) in [("Latin+Klingon", "Klingon"), ("Wsp", "Wsp")] {
+            match parse_unicode_block_filter(expr) {
+                Err(Error::InvalidFilterName(n)) => assert_eq!(n, name),
+                r => panic!("{}: {:?}", expr, r),
+            }
+        }
+        // `All-Ctrlx` is not the name `All-Ctrl` followed by `x`.
+        for (expr, name) in [("Latin", "Latin"), ("All-Ctrlx", "Ctrlx")] {
+            match parse_ascii_filter(expr) {
+                Err(Error::InvalidFilterName(n)) => assert_eq!(n, name),
+                r => panic!("{}: {:?}", expr, r),
+            }
+        }
+    }
+}
@@ -1,26 +1,46 @@
-use crate::{input::ByteCounter, mission::Mission};
-use std::{io::Write, str, sync::Arc};
+use crate::{
+    input::{ByteCounter, FailedInput, InputFileId, InputLabel},
+    keywords::KeywordMatch,
+    language::LanguageGuess,
+    mission::Mission,
+};
+use std::{str, sync::Arc};
 
 #[cfg(not(test))]
 pub const OUTPUT_BUF_LEN: usize = 0x9192;
 #[cfg(test)]
 pub const OUTPUT_BUF_LEN: usize = 0x40;
 
-#[derive(Debug, Eq, PartialEq)]
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Precision {
     Before,
     Exact,
     After,
 }
 
+impl Precision {
+    /// The marker stringsext prints in front of an offset: `<` when the
+    /// string starts somewhere before the offset, `>` when it starts
+    /// somewhere after it, and a space when the offset is exact.
+    pub fn marker(self) -> char {
+        match self {
+            Precision::Before => '<',
+            Precision::Exact => ' ',
+            Precision::After => '>',
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct Finding<'a> {
-    pub input_file_id: Option<u8>,
+    pub input_file_id: Option<InputFileId>,
     pub mission: Arc<Mission>,
     pub position: ByteCounter,
     pub position_precision: Precision,
     pub s: &'a str,
     pub s_completes_previous_s: bool,
+    pub s_is_maybe_cut: bool,
 }
 
 impl Eq for Finding<'_> {}
@@ -54,10 +74,71 @@ impl PartialOrd for Finding<'_> {
     }
 }
 
-impl<'a> Finding<'a> {
-    pub fn print(&self, out: &mut dyn Write) -> crate::Result<()> {
-        out.write_all(b"\n")?;
-        out.write_all(self.s.as_bytes())?;
-        Ok(())
+/// An owned copy of a `Finding`. Unlike `Finding`, it does not borrow from the
+/// `FindingCollection` output buffer and can therefore outlive the scan.
+#[derive(Debug, Clone)]
+pub struct OwnedFinding {
+    pub input_file_id: Option<InputFileId>,
+    /// The path or label of the input with `input_file_id`.
+    pub input_label: Arc<InputLabel>,
+    pub mission: Arc<Mission>,
+    pub position: ByteCounter,
+    pub position_precision: Precision,
+    pub s: String,
+    pub s_completes_previous_s: bool,
+    /// The string touches the end of the chunk it was found in, or was cut
+    /// at `output_line_char_nb_max`. The next finding of the same mission
+    /// may continue it, see `s_completes_previous_s`.
+    pub s_is_maybe_cut: bool,
+    /// The first keyword of the mission's keyword list found in the string,
+    /// when it starts in this finding.
+    pub keyword_match: Option<KeywordMatch>,
+    /// The `plausibility()` score of the whole string, when the mission has
+    /// a minimum score or `score` set.
+    pub score: Option<f32>,
+    /// The `detect_language()` guess for the whole string, when the mission
+    /// detects languages and one was found.
+    pub language: Option<LanguageGuess>,
+}
+
+impl OwnedFinding {
+    /// Name of the encoding the finding was decoded with. Missions created
+    /// with the `ascii` pseudo-encoding report `ascii` here.
+    pub fn encoding_name(&self) -> &'static str {
+        self.mission.encoding_name()
+    }
+}
+
+impl OwnedFinding {
+    pub fn new(finding: &Finding<'_>, input_label: Arc<InputLabel>) -> Self {
+        Self {
+            input_file_id: finding.input_file_id,
+            input_label,
+            mission: finding.mission.clone(),
+            position: finding.position,
+            position_precision: finding.position_precision,
+            s: finding.s.to_string(),
+            s_completes_previous_s: finding.s_completes_previous_s,
+            s_is_maybe_cut: finding.s_is_maybe_cut,
+            keyword_match: None,
+            score: None,
+            language: None,
+        }
     }
 }
+
+/// Everything a scan has found, in input order.
+#[derive(Debug, Default)]
+pub struct ScanResult {
+    pub findings: Vec<OwnedFinding>,
+    /// Inputs that could not be opened or read to the end, see
+    /// `OnInputError::Continue`.
+    pub failed_inputs: Vec<FailedInput>,
+}
+
+/// What a scan reports besides its findings, returned by
+/// `StringsScanner::run_with()`.
+#[derive(Debug, Default)]
+pub struct ScanSummary {
+    pub failed_inputs: Vec<FailedInput>,
+}
@@ -2,14 +2,14 @@ use crate::{
     as_mut_str_unchecked_no_borrow_check, as_str_unchecked_no_borrow_check,
     finding::{Finding, Precision, OUTPUT_BUF_LEN},
     helper::{starts_with_multibyte_char, SplitStr},
-    input::{ByteCounter, INPUT_BUF_LEN},
+    input::{ByteCounter, InputFileId},
     scanner::ScannerState,
 };
 use encoding_rs::DecoderResult;
 use std::{
-    io::Write,
+    cmp,
     marker::PhantomPinned,
-    ops::Deref,
+    ops::{Deref, Range},
     pin::Pin,
     slice, str,
     sync::{Arc, Mutex},
@@ -20,29 +20,45 @@ pub struct FindingCollection<'a> {
     pub v: Vec<Finding<'a>>,
     pub first_byte_position: ByteCounter,
     output_buffer_bytes: Box<[u8]>,
-    pub str_buf_overflow: bool,
+    /// Output buffers that ran full. They are kept, because findings in `v`
+    /// still point into them.
+    full_output_buffers: Vec<Box<[u8]>>,
     _marker: PhantomPinned,
 }
 impl FindingCollection<'_> {
-    pub fn new(byte_offset: ByteCounter) -> Self {
-        let output_buffer_bytes = Box::new([0u8; OUTPUT_BUF_LEN]);
+    /// Creates an empty collection with an output buffer of at least
+    /// `OUTPUT_BUF_LEN` bytes.
+    pub fn new(byte_offset: ByteCounter, output_buffer_len: usize) -> Self {
+        let output_buffer_bytes =
+            vec![0u8; cmp::max(output_buffer_len, OUTPUT_BUF_LEN)].into_boxed_slice();
         FindingCollection {
             v: Vec::new(),
             first_byte_position: byte_offset,
             output_buffer_bytes,
-            str_buf_overflow: false,
+            full_output_buffers: Vec::new(),
             _marker: PhantomPinned,
         }
     }
 
     pub fn from<'a>(
         ss: Arc<Mutex<ScannerState>>,
-        input_file_id: Option<u8>,
+        input_file_id: Option<InputFileId>,
         input_buffer: &[u8],
         is_last_input_buffer: bool,
     ) -> Pin<Box<FindingCollection<'a>>> {
         let mut ss = ss.lock().unwrap();
-        let mut fc = FindingCollection::new(ss.consumed_bytes);
+        // The output buffer must hold the leftover of the last run and
+        // everything the decoder produces from `input_buffer`. Tests start
+        // with a small buffer, to run into `OutputFull`.
+        let output_buffer_len = if cfg!(test) {
+            ss.last_scan_run_leftover.len() + OUTPUT_BUF_LEN
+        } else {
+            ss.decoder
+                .max_utf8_buffer_length_without_replacement(input_buffer.len())
+                .and_then(|n| n.checked_add(ss.last_scan_run_leftover.len()))
+                .unwrap_or(OUTPUT_BUF_LEN)
+        };
+        let mut fc = FindingCollection::new(ss.consumed_bytes, output_buffer_len);
         let mut extra_round = false;
         let mut decoder_input_start = 0usize;
         let mut decoder_input_end;
@@ -60,7 +76,11 @@ impl FindingCollection<'_> {
             ss.last_run_str_was_printed_and_is_maybe_cut_str;
         let decoder_input_window = 2 * ss.mission.output_line_char_nb_max;
         let mut is_last_window = false;
-        '_input_window_loop: while decoder_input_start < input_buffer.len() {
+        // When the input ended, we run at least once, even with an empty
+        // `input_buffer`, to flush the decoder and the leftover of the last run.
+        '_input_window_loop: while decoder_input_start < input_buffer.len()
+            || (is_last_input_buffer && !extra_round)
+        {
             decoder_input_end = match decoder_input_start + decoder_input_window {
                 n if n < input_buffer.len() => n,
                 _ => {
@@ -115,7 +135,11 @@ impl FindingCollection<'_> {
                     && decoder_result != DecoderResult::OutputFull)
                     || (is_last_window && is_last_input_buffer);
                 let continue_str_if_possible = last_window_str_was_printed_and_is_maybe_cut_str;
-                last_window_str_was_printed_and_is_maybe_cut_str = false;
+                // A run without any output, e.g. after `OutputFull`, does not
+                // end a cut string, unless invalid bytes follow.
+                if !split_str_buffer.is_empty() || invalid_bytes_after_split_str_buffer {
+                    last_window_str_was_printed_and_is_maybe_cut_str = false;
+                }
 
                 '_chunk_loop: for chunk in SplitStr::new(
                     split_str_buffer,
@@ -124,6 +148,8 @@ impl FindingCollection<'_> {
                     continue_str_if_possible,
                     invalid_bytes_after_split_str_buffer,
                     ss.mission.filter,
+                    ss.mission.script_filter.as_deref(),
+                    ss.mission.reject_unassigned,
                     ss.mission.output_line_char_nb_max,
                 ) {
                     if !chunk.s_is_to_be_filtered_again {
@@ -134,6 +160,7 @@ impl FindingCollection<'_> {
                             position_precision,
                             s: chunk.s,
                             s_completes_previous_s: chunk.s_completes_previous_s,
+                            s_is_maybe_cut: chunk.s_is_maybe_cut,
                         });
 
                         last_window_leftover_len = 0;
@@ -158,19 +185,21 @@ impl FindingCollection<'_> {
                         }
                     }
                     DecoderResult::OutputFull => {
-                        fc.clear_and_mark_incomplete();
-                        eprintln!("Buffer overflow. Output buffer is too small to receive all decoder data.\
-                            Some findings got lost in input {:x}..{:x} from file {:?} for scanner ({})!",
-                            ss.consumed_bytes,
-                            ss.consumed_bytes + decoder_input_start as ByteCounter,
-                            input_file_id,
-                            char::from(ss.mission.mission_id + 97)
+                        // Continue in a new buffer, large enough for the rest
+                        // of `input_buffer`, and carry the leftover over.
+                        let output_buffer_len = ss
+                            .decoder
+                            .max_utf8_buffer_length_without_replacement(
+                                input_buffer.len() - decoder_input_start,
+                            )
+                            .and_then(|n| n.checked_add(last_window_leftover_len))
+                            .unwrap_or(0)
+                            .max(fc.output_buffer_bytes.len());
+                        fc.replace_full_output_buffer(
+                            output_buffer_len,
+                            decoder_output_start - last_window_leftover_len..decoder_output_start,
                         );
-                        decoder_output_start = 0;
-                        debug_assert!(
-                        true,
-                        "Buffer overflow. Output buffer is too small to receive all decoder data."
-                    );
+                        decoder_output_start = last_window_leftover_len;
                     }
                     DecoderResult::Malformed(_, _) => {}
                 };
@@ -187,25 +216,14 @@ impl FindingCollection<'_> {
         Box::pin(fc)
     }
 
-    pub fn clear_and_mark_incomplete(&mut self) {
-        self.v.clear();
-        self.str_buf_overflow = true;
-    }
-
-    #[allow(dead_code)]
-    pub fn print(&self, out: &mut dyn Write) -> crate::Result<()> {
-        if self.str_buf_overflow {
-            eprint!("Warning: output buffer overflow! Some findings might got lost.");
-            eprintln!(
-                "in input chunk 0x{:x}-0x{:x}.",
-                self.first_byte_position,
-                self.first_byte_position + INPUT_BUF_LEN as ByteCounter
-            );
-        }
-        for finding in &self.v {
-            finding.print(out)?;
-        }
-        Ok(())
+    /// Moves the full output buffer aside and continues with a new one of
+    /// `output_buffer_len` bytes, starting with the bytes in `leftover` of
+    /// the old one.
+    fn replace_full_output_buffer(&mut self, output_buffer_len: usize, leftover: Range<usize>) {
+        let mut output_buffer_bytes = vec![0u8; output_buffer_len].into_boxed_slice();
+        output_buffer_bytes[..leftover.len()].copy_from_slice(&self.output_buffer_bytes[leftover]);
+        let full = std::mem::replace(&mut self.output_buffer_bytes, output_buffer_bytes);
+        self.full_output_buffers.push(full);
     }
 }
 
@@ -243,3 +261,77 @@ impl<'a> Deref for FindingCollection<'a> {
         &self.v
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::{MissionConfig, Missions, UBF_ALL};
+    use encoding_rs::{Encoding, UTF_16LE, UTF_8};
+
+    /// Scans `input` in chunks of `chunk_size` bytes, like the `Slicer`.
+    /// Returns the strings, with parts joined by `s_completes_previous_s`,
+    /// and the number of output buffers that ran full.
+    fn scan(encoding: &'static Encoding, input: &[u8], chunk_size: usize) -> (Vec<String>, usize) {
+        let config = MissionConfig::new(encoding).unicode_block_filter(UBF_ALL);
+        let missions = Missions::from_configs(vec![config]).unwrap();
+        let ss = Arc::new(Mutex::new(ScannerState::new(missions[0].clone())));
+        let mut strings: Vec<String> = Vec::new();
+        let mut full_output_buffers = 0;
+        for chunk in input.chunks(chunk_size).chain([&[][..]]) {
+            let fc = FindingCollection::from(ss.clone(), Some(1), chunk, chunk.is_empty());
+            full_output_buffers += fc.full_output_buffers.len();
+            for finding in &fc {
+                match strings.last_mut() {
+                    Some(last) if finding.s_completes_previous_s => last.push_str(finding.s),
+                    _ => strings.push(finding.s.to_string()),
+                }
+            }
+        }
+        (strings, full_output_buffers)
+    }
+
+    fn words() -> Vec<String> {
+        let mut words: Vec<String> = (0..60)
+            .map(|i| match i % 4 {
+                0 => format!("word{}", i),
+                1 => format!("Grüße aus Köln {}", i),
+                2 => format!("строка номер {}", i),
+                _ => "x".repeat(20 + 7 * i),
+            })
+            .collect();
+        words.push("long ".repeat(100));
+        words
+    }
+
+    fn encode(words: &[String], encoding: &'static Encoding) -> Vec<u8> {
+        let mut input = Vec::new();
+        for word in words {
+            if encoding == UTF_16LE {
+                input.extend(word.encode_utf16().flat_map(u16::to_le_bytes));
+            } else {
+                input.extend_from_slice(word.as_bytes());
+            }
+            input.extend_from_slice(&[0, 0]);
+        }
+        input
+    }
+
+    #[test]
+    fn output_full_loses_and_repeats_nothing() {
+        let words = words();
+        for encoding in [UTF_8, UTF_16LE] {
+            let input = encode(&words, encoding);
+            for chunk_size in [input.len(), 1000, 333] {
+                let (strings, full_output_buffers) = scan(encoding, &input, chunk_size);
+                assert!(full_output_buffers > 0);
+                assert_eq!(
+                    strings,
+                    words,
+                    "{}, chunk size {}",
+                    encoding.name(),
+                    chunk_size
+                );
+            }
+        }
+    }
+}
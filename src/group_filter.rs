@@ -0,0 +1,279 @@
+//! Regular expression, keyword and score filtering, and language detection
+//! of findings.
+//!
+//! A string can reach the caller in several findings: it is cut at
+//! `output_line_char_nb_max`, and at chunk boundaries. The parts following
+//! the first have `s_completes_previous_s` set. The regular expressions and
+//! keywords of a mission are searched in the assembled string, i.e. the
+//! concatenation of all parts, so that matches spanning a cut are found,
+//! and the score and language are computed for it. Then all parts are kept
+//! or all are dropped. A keyword match is reported with the part it starts
+//! in, the score and language with all parts.
+//!
+//! A group stays open as long as its last part `s_is_maybe_cut`. In the
+//! meantime, all later findings, of any mission, are held back to keep the
+//! order. To bound the memory this takes, a group is also decided on once
+//! its string reaches `GROUP_LEN_MAX` bytes. The rest of such a long string
+//! forms the next group, so matches spanning that cut are missed.
+
+use crate::{
+    finding::OwnedFinding,
+    keywords::KeywordMatch,
+    language::detect_language,
+    mission::{Mission, Missions},
+    score::plausibility,
+};
+use std::collections::VecDeque;
+
+/// Length in bytes at which an open group is decided on.
+#[cfg(not(test))]
+pub const GROUP_LEN_MAX: usize = 0x10_0000;
+
+#[cfg(test)]
+pub const GROUP_LEN_MAX: usize = 0x40;
+
+/// Filters the findings of one job, chunk by chunk.
+pub struct GroupFilter {
+    /// Findings not handed out yet, with the decision about them, if made.
+    queue: VecDeque<(OwnedFinding, Option<bool>)>,
+    /// Number of findings handed out or dropped so far. `queue[0]` has this
+    /// sequence number.
+    dequeued: usize,
+    /// The open group of every mission.
+    open_groups: Vec<Option<OpenGroup>>,
+}
+
+/// Parts of a string seen so far.
+struct OpenGroup {
+    /// Sequence numbers of the parts.
+    parts: Vec<usize>,
+    /// Byte positions of the parts in `s`.
+    starts: Vec<usize>,
+    /// The concatenated parts.
+    s: String,
+}
+
+impl GroupFilter {
+    /// `None` when no mission has a regular expression, keywords, a minimum
+    /// score, or reports scores or detects languages, so there is nothing
+    /// to do.
+    pub fn new(missions: &Missions) -> Option<Self> {
+        missions
+            .iter()
+            .any(|mission| filters(mission))
+            .then(|| Self {
+                queue: VecDeque::new(),
+                dequeued: 0,
+                open_groups: missions.iter().map(|_| None).collect(),
+            })
+    }
+
+    /// Takes the findings of the next chunk and returns those that are
+    /// decided on and accepted. At the end of the job, `is_last` closes
+    /// all groups.
+    pub fn filter(&mut self, findings: Vec<OwnedFinding>, is_last: bool) -> Vec<OwnedFinding> {
+        for finding in findings {
+            self.push(finding);
+        }
+        if is_last {
+            for mission_id in 0..self.open_groups.len() {
+                self.close(mission_id);
+            }
+        }
+        let mut accepted = Vec::new();
+        while let Some((_, Some(_))) = self.queue.front() {
+            let (finding, pass) = self.queue.pop_front().unwrap();
+            self.dequeued += 1;
+            if pass == Some(true) {
+                accepted.push(finding);
+            }
+        }
+        accepted
+    }
+
+    fn push(&mut self, finding: OwnedFinding) {
+        let mission_id = finding.mission.mission_id;
+        let seq = self.dequeued + self.queue.len();
+        if !filters(&finding.mission) {
+            self.queue.push_back((finding, Some(true)));
+            return;
+        }
+        if !finding.s_completes_previous_s {
+            self.close(mission_id);
+        }
+        let group = self.open_groups[mission_id].get_or_insert_with(|| OpenGroup {
+            parts: Vec::new(),
+            starts: Vec::new(),
+            s: String::new(),
+        });
+        group.parts.push(seq);
+        group.starts.push(group.s.len());
+        group.s.push_str(&finding.s);
+        let is_maybe_cut = finding.s_is_maybe_cut;
+        let is_full = group.s.len() >= GROUP_LEN_MAX;
+        self.queue.push_back((finding, None));
+        if !is_maybe_cut || is_full {
+            self.close(mission_id);
+        }
+    }
+
+    /// Decides about the open group of `mission_id`, if any.
+    fn close(&mut self, mission_id: usize) {
+        if let Some(group) = self.open_groups[mission_id].take() {
+            let mission = self.queue[group.parts[0] - self.dequeued].0.mission.clone();
+            let mut pass = accepts(&mission, &group.s);
+            let score =
+                (mission.score || mission.min_score.is_some()).then(|| plausibility(&group.s));
+            if let (Some(score), Some(min_score)) = (score, mission.min_score) {
+                pass &= score >= min_score;
+            }
+            if let (true, Some(keywords)) = (pass, &mission.keywords) {
+                match keywords.find(&group.s) {
+                    Some((keyword, pos)) => {
+                        let part = group.starts.partition_point(|&start| start <= pos) - 1;
+                        let start = group.starts[part];
+                        let keyword_match = KeywordMatch {
+                            keyword: keyword.clone(),
+                            char_offset: group.s[start..pos].chars().count(),
+                        };
+                        let finding = &mut self.queue[group.parts[part] - self.dequeued].0;
+                        finding.keyword_match = Some(keyword_match);
+                    }
+                    None => pass = false,
+                }
+            }
+            let language = (pass && mission.detect_language)
+                .then(|| detect_language(&group.s))
+                .flatten();
+            for seq in group.parts {
+                let (finding, decision) = &mut self.queue[seq - self.dequeued];
+                finding.score = score;
+                finding.language = language;
+                *decision = Some(pass);
+            }
+        }
+    }
+}
+
+/// Has the mission regular expressions, keywords, a minimum score, or does
+/// it report scores or detect languages?
+fn filters(mission: &Mission) -> bool {
+    mission.regex.is_some()
+        || mission.exclude_regex.is_some()
+        || mission.keywords.is_some()
+        || mission.min_score.is_some()
+        || mission.score
+        || mission.detect_language
+}
+
+/// Does `s` match the mission's regular expression and not its excluding
+/// one?
+fn accepts(mission: &Mission, s: &str) -> bool {
+    mission.regex.as_ref().is_none_or(|regex| regex.is_match(s))
+        && !mission
+            .exclude_regex
+            .as_ref()
+            .is_some_and(|regex| regex.is_match(s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::GROUP_LEN_MAX;
+    use crate::{MissionConfig, StringsScanner};
+    use regex::Regex;
+
+    const TEXT: &str = "0123456789abcdefghijklmnopqrstuvwxyz";
+
+    /// Scans `TEXT`, surrounded by zeros, in chunks of `chunk_size` and
+    /// returns the strings found.
+    fn scan(mission: MissionConfig, chunk_size: usize) -> Vec<String> {
+        let mut bytes = vec![0; 10];
+        bytes.extend_from_slice(TEXT.as_bytes());
+        bytes.extend_from_slice(&[0; 10]);
+        let scanner = StringsScanner::builder()
+            .mission(mission)
+            .chunk_size(chunk_size)
+            .build()
+            .unwrap();
+        let result = scanner.scan_bytes(&bytes).unwrap();
+        result
+            .findings
+            .into_iter()
+            .map(|finding| finding.s)
+            .collect()
+    }
+
+    /// Checks that `pattern` is found across a cut of `TEXT` only.
+    fn assert_filtered_as_a_whole(mission: MissionConfig, chunk_size: usize, pattern: &str) {
+        let parts = scan(mission.clone(), chunk_size);
+        assert!(parts.len() > 1);
+        assert_eq!(parts.concat(), TEXT);
+        assert!(parts.iter().all(|part| !part.contains(pattern)));
+
+        let regex = Some(Regex::new(pattern).unwrap());
+        let kept = scan(mission.clone().regex(regex.clone()), chunk_size);
+        assert_eq!(kept, parts);
+        let dropped = scan(mission.clone().exclude_regex(regex), chunk_size);
+        assert!(dropped.is_empty());
+        // Anchored at both ends of the assembled string.
+        let whole = Some(Regex::new(&format!("^{}$", TEXT)).unwrap());
+        assert_eq!(scan(mission.regex(whole), chunk_size), parts);
+    }
+
+    #[test]
+    fn scores_are_reported_on_request() {
+        let scores = |mission: MissionConfig| {
+            let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+            let result = scanner.scan_bytes(b"\0hello world\0").unwrap();
+            result
+                .findings
+                .iter()
+                .map(|finding| finding.score.is_some())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scores(MissionConfig::ascii()), [false]);
+        assert_eq!(scores(MissionConfig::ascii().score(true)), [true]);
+        assert_eq!(scores(MissionConfig::ascii().min_score(Some(0.0))), [true]);
+    }
+
+    #[test]
+    fn match_spanning_an_output_line_cut() {
+        let mission = MissionConfig::ascii().output_line_len(8);
+        assert_filtered_as_a_whole(mission, 0x100, "6789ab");
+    }
+
+    #[test]
+    fn long_strings_are_decided_on_in_pieces() {
+        let mut bytes = vec![0; 4];
+        bytes.resize(4 + 100, b'a');
+        bytes.extend_from_slice(b"z\0");
+        let mission = MissionConfig::ascii()
+            .output_line_len(8)
+            .regex(Some(Regex::new("z").unwrap()));
+        let scanner = StringsScanner::builder()
+            .mission(mission)
+            .chunk_size(16)
+            .build()
+            .unwrap();
+        let result = scanner.scan_bytes(&bytes).unwrap();
+        let parts: Vec<_> = result.findings.into_iter().map(|f| f.s).collect();
+        // The first group ends within a part after `GROUP_LEN_MAX` bytes,
+        // without the `z`, the rest is kept.
+        let kept = parts.concat();
+        assert!(kept.ends_with("az"));
+        assert!(kept.len() <= 101 - GROUP_LEN_MAX);
+        assert!(kept.len() > 101 - GROUP_LEN_MAX - 8);
+    }
+
+    #[test]
+    fn match_spanning_a_chunk_cut() {
+        // The first line is assembled across chunks, so the cut at byte 32
+        // shows up in the second line, after `kl`.
+        let mission = MissionConfig::ascii().output_line_len(20);
+        assert_eq!(
+            scan(mission.clone(), 16),
+            ["0123456789abcdefghij", "kl", "mnopqrstuvwxyz"]
+        );
+        assert_filtered_as_a_whole(mission, 16, "klmnop");
+    }
+}
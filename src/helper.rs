@@ -1,6 +1,9 @@
 //! Small functions of general use, mainly used in module `scanner`.
 
-use crate::mission::Utf8Filter;
+use crate::{
+    mission::Utf8Filter,
+    script::{unassigned, ScriptFilter},
+};
 use std::{slice, str};
 
 /// This macro is useful for zero-cost conversion from &[u8] to &str. Use
@@ -48,7 +51,7 @@ macro_rules! as_mut_slice_no_borrow_check {
 
 /// This struct defines the state of the iterator `SplitStr`.
 #[allow(dead_code)]
-pub struct SplitStr<'a> {
+pub struct SplitStr<'a, 'f> {
     /// The buffer where `next()` searches for substrings satisfying
     /// certain conditions.
     inp: &'a str,
@@ -108,6 +111,14 @@ pub struct SplitStr<'a> {
     /// forwarded further.
     utf8f: Utf8Filter,
 
+    /// When set, multi-byte characters are checked against their Unicode
+    /// script instead of `Utf8Filter::ubf`.
+    script_filter: Option<&'f ScriptFilter>,
+
+    /// When set, unassigned code points and non-characters do not pass,
+    /// whatever the other filters say.
+    unassigned: Option<&'static ScriptFilter>,
+
     /// This imposes an additional constraint to the iterator and instructs him
     /// to never return substrings longer than `s_char_nb_max`.
     s_char_nb_max: usize,
@@ -157,17 +168,19 @@ pub struct SplitStrResult<'a> {
     /// exception. When he does, he sets also flag, so the caller can know.
     pub s_satisfies_min_char_rule: bool,
 }
-impl<'a> SplitStr<'a> {
+impl<'a, 'f> SplitStr<'a, 'f> {
     #[inline]
     pub fn new(
-        inp: &str,
+        inp: &'a str,
         chars_min_nb: u8,
         require_same_unicode_block: bool,
         last_s_was_maybe_cut: bool,
         invalid_bytes_after_inp: bool,
         utf8f: Utf8Filter,
+        script_filter: Option<&'f ScriptFilter>,
+        reject_unassigned: bool,
         s_char_nb_max: usize,
-    ) -> SplitStr {
+    ) -> SplitStr<'a, 'f> {
         unsafe {
             SplitStr {
                 // Input buffer.
@@ -185,6 +198,8 @@ impl<'a> SplitStr<'a> {
                 // We will set this to false later, if `utf8f.grep_char` requires some
                 // additional checking.
                 utf8f,
+                script_filter,
+                unassigned: reject_unassigned.then(unassigned),
                 s_char_nb_max,
             }
         }
@@ -194,7 +209,7 @@ impl<'a> SplitStr<'a> {
 /// The iterator's `next()` returns some `SplitStrResult`-object, which is
 /// essentially a substring `&str` pointing into a
 /// `FindingCollection::output_buffer_bytes` with some additional information.
-impl<'a> Iterator for SplitStr<'a> {
+impl<'a> Iterator for SplitStr<'a, '_> {
     type Item = SplitStrResult<'a>;
 
     #[inline]
@@ -267,7 +282,18 @@ impl<'a> Iterator for SplitStr<'a> {
                 (self.utf8f.pass_af_filter(leading_byte), true)
             } else {
                 // char_len > 1
-                if self.utf8f.pass_ubf_filter(leading_byte) {
+                let char_passes = match (self.script_filter, self.unassigned) {
+                    (None, None) => self.utf8f.pass_ubf_filter(leading_byte),
+                    (script_filter, unassigned) => {
+                        let cp = unsafe { decode_multibyte_char(self.p, char_len) };
+                        !unassigned.is_some_and(|unassigned| unassigned.pass(cp))
+                            && match script_filter {
+                                Some(script_filter) => script_filter.pass(cp),
+                                None => self.utf8f.pass_ubf_filter(leading_byte),
+                            }
+                    }
+                };
+                if char_passes {
                     #[allow(clippy::branches_sharing_code)]
                     if !self.require_same_unicode_block
                         || leading_byte == last_multi_char_leading_byte
@@ -423,6 +449,18 @@ impl<'a> Iterator for SplitStr<'a> {
     }
 }
 
+/// Decodes the multi-byte character of `char_len` bytes at `p`.
+///
+/// # Safety
+///
+/// `p` must point to the leading byte of a valid UTF-8 character of
+/// `char_len` bytes.
+#[inline]
+unsafe fn decode_multibyte_char(p: *const u8, char_len: usize) -> u32 {
+    let leading_bits = (*p as u32) & (0x7f >> char_len);
+    (1..char_len).fold(leading_bits, |cp, i| (cp << 6) | (*p.add(i) as u32 & 0x3f))
+}
+
 /// Small helper function that tests if some UTF-8 string starts with a
 /// multi-byte-character.
 #[inline]
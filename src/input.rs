@@ -1,89 +1,315 @@
 use crate::as_mut_slice_no_borrow_check;
 use std::{
+    borrow::Cow,
+    cmp, fmt,
     fs::File,
     io::{self, Read},
-    iter::Peekable,
     path::{Path, PathBuf},
     slice,
-    vec::IntoIter,
+    sync::Arc,
 };
 
 pub type ByteCounter = u64;
 
+/// Number of an input in the order the inputs were given, counting from 1.
+pub type InputFileId = usize;
+
 #[cfg(not(test))]
 pub const INPUT_BUF_LEN: usize = 4096;
 
 #[cfg(test)]
 pub const INPUT_BUF_LEN: usize = 0x20;
 
-pub struct Slicer {
-    filename_iter: Peekable<IntoIter<PathBuf>>,
-    reader: Box<dyn Read>,
-    current_input_idx: usize,
-    current_input_is_last: bool,
-    input_buffer: [u8; INPUT_BUF_LEN],
+/// One input stream to scan.
+pub enum Input<'a> {
+    /// A file, opened when the scan reaches it.
+    Path(PathBuf),
+    /// A buffer already in memory.
+    Bytes(&'a [u8]),
+    /// Any other byte stream, e.g. a pipe, a socket or a decompressor.
+    Reader(Box<dyn Read + Send + 'a>),
+    /// The standard input of the process.
+    Stdin,
+    /// Another input, reported with a user-supplied label instead of its
+    /// path or number, see `Input::labeled()`.
+    Labeled(String, Box<Input<'a>>),
 }
 
-impl Slicer {
-    #[inline]
-    pub fn new(inputs: Vec<PathBuf>) -> Self {
-        let mut filename_iter = inputs.into_iter().peekable();
-        let filename = filename_iter.next().unwrap();
-        let reader = match File::open(Path::new(&filename)) {
-            Ok(file) => Box::new(file) as Box<dyn Read>,
-            Err(e) => {
-                eprintln!("Error: can not read file`{:?}`: {}", filename, e);
-                Box::new(io::empty()) as Box<dyn Read>
+/// Names the input a finding was found in.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum InputLabel {
+    Path(PathBuf),
+    Stdin,
+    /// A user-supplied label, or `#<input_file_id>` for buffers and readers
+    /// without one.
+    Label(String),
+}
+
+impl fmt::Display for InputLabel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InputLabel::Path(path) => write!(f, "{}", path.display()),
+            InputLabel::Stdin => f.write_str("-"),
+            InputLabel::Label(label) => f.write_str(label),
+        }
+    }
+}
+
+/// An input that could not be opened or read to the end.
+#[derive(Debug)]
+pub struct FailedInput {
+    pub input_file_id: InputFileId,
+    pub input_label: Arc<InputLabel>,
+    pub error: io::Error,
+}
+
+impl From<FailedInput> for crate::error::Error {
+    fn from(failed: FailedInput) -> Self {
+        let input_label = Arc::try_unwrap(failed.input_label).unwrap_or_else(|l| (*l).clone());
+        crate::error::Error::Input(input_label, failed.error)
+    }
+}
+
+impl<'a> Input<'a> {
+    /// Interprets a command line argument: `-` stands for stdin, anything
+    /// else for a file path.
+    pub fn from_arg(arg: &str) -> Self {
+        if arg == "-" {
+            Input::Stdin
+        } else {
+            Input::Path(PathBuf::from(arg))
+        }
+    }
+
+    pub fn reader<R: Read + Send + 'a>(reader: R) -> Self {
+        Input::Reader(Box::new(reader))
+    }
+
+    /// Reports findings in this input with `label` instead of its path or
+    /// number.
+    pub fn labeled<S: Into<String>>(self, label: S) -> Self {
+        match self {
+            Input::Labeled(_, input) => Input::Labeled(label.into(), input),
+            input => Input::Labeled(label.into(), Box::new(input)),
+        }
+    }
+
+    /// Splits off the label findings in this input are reported with.
+    pub fn into_labeled(self, input_file_id: InputFileId) -> (InputLabel, Self) {
+        match self {
+            Input::Path(path) => (InputLabel::Path(path.clone()), Input::Path(path)),
+            Input::Stdin => (InputLabel::Stdin, Input::Stdin),
+            Input::Labeled(label, input) => (InputLabel::Label(label), *input),
+            input => (InputLabel::Label(format!("#{}", input_file_id)), input),
+        }
+    }
+
+    /// Splits off the labels of all `inputs`, numbering them from 1. Done
+    /// before any file is memory-mapped, so mapped files keep their path.
+    pub fn label_all(inputs: Vec<Self>) -> Vec<(InputLabel, Self)> {
+        inputs
+            .into_iter()
+            .enumerate()
+            .map(|(idx, input)| input.into_labeled(idx + 1))
+            .collect()
+    }
+
+    /// Opens the stream.
+    fn open(self) -> io::Result<Stream<'a>> {
+        Ok(match self {
+            Input::Path(filename) => Stream::Reader(Box::new(File::open(filename)?)),
+            Input::Bytes(bytes) => Stream::Bytes(bytes),
+            Input::Reader(reader) => Stream::Reader(reader),
+            Input::Stdin => Stream::Reader(Box::new(io::stdin())),
+            Input::Labeled(_, input) => input.open()?,
+        })
+    }
+}
+
+/// Memory-maps all `Input::Path` inputs, labeled or not. The returned
+/// vector has one entry per input, `None` for inputs that are not files or
+/// that can not be mapped. These are read as usual. See
+/// `ScannerBuilder::mmap()`.
+#[cfg(feature = "mmap")]
+pub fn map_files(inputs: &[(InputLabel, Input)]) -> Vec<Option<memmap2::Mmap>> {
+    inputs.iter().map(|(_, input)| map_file(input)).collect()
+}
+
+#[cfg(feature = "mmap")]
+fn map_file(input: &Input) -> Option<memmap2::Mmap> {
+    match input {
+        Input::Path(path) => File::open(path)
+            // SAFETY: The map is handed out as `&[u8]`, which must not
+            // change while it is borrowed. Another process can still
+            // modify or truncate the file: the scan then sees the new
+            // bytes, and reading pages beyond a truncated end raises
+            // SIGBUS. We accept this for the `mmap` opt-in, like other
+            // tools mapping their inputs, as the files of a scan are
+            // expected to stay put. The map outlives every slice of it,
+            // see `StringsScanner::with_mapped_inputs()`.
+            .and_then(|file| unsafe { memmap2::Mmap::map(&file) })
+            .ok(),
+        Input::Labeled(_, input) => map_file(input),
+        _ => None,
+    }
+}
+
+impl From<PathBuf> for Input<'_> {
+    fn from(path: PathBuf) -> Self {
+        Input::Path(path)
+    }
+}
+
+impl From<&Path> for Input<'_> {
+    fn from(path: &Path) -> Self {
+        Input::Path(path.to_path_buf())
+    }
+}
+
+impl<'a> From<&'a [u8]> for Input<'a> {
+    fn from(bytes: &'a [u8]) -> Self {
+        Input::Bytes(bytes)
+    }
+}
+
+impl fmt::Debug for Input<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Input::Path(path) => f.debug_tuple("Path").field(path).finish(),
+            Input::Bytes(bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+            Input::Reader(_) => f.write_str("Reader"),
+            Input::Stdin => f.write_str("Stdin"),
+            Input::Labeled(label, input) => {
+                f.debug_tuple("Labeled").field(label).field(input).finish()
             }
-        };
-        let current_input_is_last = filename_iter.peek().is_none();
-
-        Self {
-            filename_iter,
-            reader,
-            current_input_idx: 1,
-            current_input_is_last,
-            input_buffer: [0u8; INPUT_BUF_LEN],
         }
     }
 }
 
-impl Iterator for Slicer {
-    type Item = (Vec<u8>, Option<u8>, bool);
+/// An opened `Input`.
+enum Stream<'a> {
+    Reader(Box<dyn Read + 'a>),
+    /// In-memory input. Its slices are handed out without copying.
+    Bytes(&'a [u8]),
+}
+
+pub struct Slicer<'a> {
+    stream: Stream<'a>,
+    input_file_id: Option<InputFileId>,
+    chunk_size: usize,
+    /// Set after the input stream ended and the final, empty slice was
+    /// handed out.
+    input_ended: bool,
+    input_buffer: Box<[u8]>,
+}
+
+impl<'a> Slicer<'a> {
+    /// Opens `input` and cuts it into slices of at most `chunk_size` bytes.
+    #[inline]
+    pub fn new(
+        input: Input<'a>,
+        input_file_id: Option<InputFileId>,
+        chunk_size: usize,
+    ) -> io::Result<Self> {
+        debug_assert!(chunk_size > 0);
+        Ok(Self {
+            stream: input.open()?,
+            input_file_id,
+            chunk_size,
+            input_ended: false,
+            input_buffer: vec![0u8; chunk_size].into_boxed_slice(),
+        })
+    }
+}
+
+impl<'a> Iterator for Slicer<'a> {
+    /// After a read error, the error is the last item.
+    type Item = io::Result<(Cow<'a, [u8]>, Option<InputFileId>, bool)>;
     fn next(&mut self) -> Option<Self::Item> {
-        let input_buffer_slice = as_mut_slice_no_borrow_check!(self.input_buffer);
-        let no_bytes_received = self.reader.read(input_buffer_slice).unwrap_or_else(|_| {
-            panic!(
-                "Error: Could not read input stream no. {}",
-                self.current_input_idx
-            )
-        });
-        let result = input_buffer_slice[..no_bytes_received].to_vec();
-        let this_stream_ended = no_bytes_received == 0;
-        let input_ended = self.current_input_is_last && this_stream_ended;
-
-        if this_stream_ended {
-            if self.current_input_is_last {
-                return None;
-            } else {
-                let filename = self.filename_iter.next().unwrap();
-                self.current_input_idx += 1;
-                self.current_input_is_last = self.filename_iter.peek().is_none();
-                let reader = match File::open(Path::new(&filename)) {
-                    Ok(file) => Box::new(file) as Box<dyn Read>,
-                    Err(e) => {
-                        eprintln!("Error: can not read file: {}", e);
-                        Box::new(io::empty()) as Box<dyn Read>
+        if self.input_ended {
+            return None;
+        }
+        let result = match &mut self.stream {
+            Stream::Bytes(rest) => {
+                let bytes: &'a [u8] = rest;
+                let (chunk, tail) = bytes.split_at(cmp::min(self.chunk_size, bytes.len()));
+                *rest = tail;
+                Cow::Borrowed(chunk)
+            }
+            Stream::Reader(reader) => {
+                let input_buffer_slice = as_mut_slice_no_borrow_check!(self.input_buffer);
+                let no_bytes_received = loop {
+                    match reader.read(input_buffer_slice) {
+                        Ok(n) => break n,
+                        Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                        Err(e) => {
+                            self.input_ended = true;
+                            return Some(Err(e));
+                        }
                     }
                 };
-                self.reader = reader;
+                Cow::Owned(input_buffer_slice[..no_bytes_received].to_vec())
             }
         };
+        // We hand out one last empty slice flagged `input_ended`, so that the
+        // scanners can flush strings touching the end of the input.
+        self.input_ended = result.is_empty();
+
+        Some(Ok((result, self.input_file_id, self.input_ended)))
+    }
+}
+
+impl<'a> Slicer<'a> {
+    /// Hands out every slice preceded by the last `overlap` bytes before it,
+    /// so that a pattern of up to `overlap + 1` bytes spanning a slice
+    /// boundary is found in one window.
+    pub fn overlapping(self, overlap: usize) -> OverlappingSlicer<'a> {
+        OverlappingSlicer {
+            slicer: self,
+            overlap,
+            window: Vec::new(),
+            position: 0,
+        }
+    }
+}
+
+/// A slice of the input, preceded by the end of the previous slice.
+pub struct Window<'w> {
+    pub bytes: &'w [u8],
+    /// Number of leading bytes, that were already part of the previous
+    /// window.
+    pub carried: usize,
+    /// Position of `bytes[0]` in the input.
+    pub position: ByteCounter,
+}
+
+/// See `Slicer::overlapping()`.
+pub struct OverlappingSlicer<'a> {
+    slicer: Slicer<'a>,
+    overlap: usize,
+    /// The previous window, then the current one.
+    window: Vec<u8>,
+    /// Position of `window[0]` in the input.
+    position: ByteCounter,
+}
 
-        let current_file_id = match self.current_input_idx {
-            0 => None,
-            c => Some(c as u8),
+impl<'a> OverlappingSlicer<'a> {
+    /// Like `Slicer::next()`, but the window borrows from `self`, so this
+    /// can not be an `Iterator`.
+    pub fn next_window(&mut self) -> Option<io::Result<Window<'_>>> {
+        let carried = self.overlap.min(self.window.len());
+        let drop = self.window.len() - carried;
+        self.window.drain(..drop);
+        self.position += drop as ByteCounter;
+        let (slice, _, _) = match self.slicer.next()? {
+            Ok(slice) => slice,
+            Err(e) => return Some(Err(e)),
         };
-        Some((result, current_file_id, input_ended))
+        self.window.extend_from_slice(&slice);
+        Some(Ok(Window {
+            bytes: &self.window,
+            carried,
+            position: self.position,
+        }))
     }
 }
@@ -0,0 +1,134 @@
+//! JSON output of findings, enabled with the `serde` feature.
+//!
+//! Every finding is serialized as one flat JSON object. The field
+//! `version` holds `JSON_SCHEMA_VERSION`, which is incremented whenever a
+//! field is renamed, removed or changes its meaning. New fields may be added
+//! without a version change.
+
+use crate::{
+    finding::{OwnedFinding, Precision},
+    input::{ByteCounter, InputFileId},
+    mission::MissionId,
+};
+use serde::{Serialize, Serializer};
+use std::io::Write;
+
+pub const JSON_SCHEMA_VERSION: u32 = 1;
+
+/// The JSON representation of an `OwnedFinding`.
+#[derive(Serialize)]
+struct Record<'a> {
+    version: u32,
+    /// Path or label of the input, `-` for stdin.
+    file: String,
+    input_file_id: Option<InputFileId>,
+    offset: ByteCounter,
+    precision: Precision,
+    encoding: &'static str,
+    mission_id: MissionId,
+    /// The filter masks are hex strings, because JSON parsers commonly
+    /// store numbers as `f64`, which can not hold 64 or 128 bit masks.
+    ascii_filter: String,
+    unicode_block_filter: String,
+    grep_char: Option<u8>,
+    /// Full names of the scripts of the mission's script filter.
+    scripts: Option<Vec<&'static str>>,
+    /// The first keyword found, when it starts in this finding, and its
+    /// offset in `string`, in characters.
+    keyword: Option<&'a str>,
+    keyword_offset: Option<usize>,
+    /// The plausibility score, from 0 to 1, when the mission computes it.
+    score: Option<f32>,
+    /// ISO 639-1 code of the detected language and the confidence, from 0
+    /// to 1.
+    language: Option<&'static str>,
+    language_confidence: Option<f32>,
+    /// The string continues the previous finding of the same mission.
+    continues_previous: bool,
+    string: &'a str,
+}
+
+impl Serialize for OwnedFinding {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        Record {
+            version: JSON_SCHEMA_VERSION,
+            file: self.input_label.to_string(),
+            input_file_id: self.input_file_id,
+            offset: self.position,
+            precision: self.position_precision,
+            encoding: self.encoding_name(),
+            mission_id: self.mission.mission_id,
+            ascii_filter: format!("{:#x}", self.mission.filter.af),
+            unicode_block_filter: format!("{:#x}", self.mission.filter.ubf),
+            grep_char: self.mission.filter.grep_char,
+            scripts: self.mission.script_filter.as_ref().map(|script_filter| {
+                script_filter
+                    .scripts()
+                    .iter()
+                    .map(|script| script.full_name())
+                    .collect()
+            }),
+            keyword: self.keyword_match.as_ref().map(|m| &*m.keyword),
+            keyword_offset: self.keyword_match.as_ref().map(|m| m.char_offset),
+            score: self.score,
+            language: self.language.map(|guess| guess.language),
+            language_confidence: self.language.map(|guess| guess.confidence),
+            continues_previous: self.s_completes_previous_s,
+            string: &self.s,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// Writes findings as JSON Lines: one JSON object per line.
+pub struct JsonLinesWriter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> JsonLinesWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    pub fn write(&mut self, finding: &OwnedFinding) -> crate::Result<()> {
+        serde_json::to_writer(&mut self.out, finding)?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{keywords::Keywords, script::ScriptFilter, MissionConfig, StringsScanner};
+
+    #[test]
+    fn a_finding_is_one_json_line() {
+        let mission = MissionConfig::ascii()
+            .keywords(Some(Keywords::new(["cat"], false).unwrap()))
+            .script_filter(Some(ScriptFilter::parse("Latn").unwrap()));
+        let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+        let result = scanner.scan_bytes(b"\0the cat\0").unwrap();
+        let mut out = JsonLinesWriter::new(Vec::new());
+        for finding in &result.findings {
+            out.write(finding).unwrap();
+        }
+        assert_eq!(
+            String::from_utf8(out.into_inner()).unwrap(),
+            concat!(
+                r##"{"version":1,"file":"#1","input_file_id":1,"offset":0,"##,
+                r#""precision":"Exact","encoding":"ascii","mission_id":0,"#,
+                r#""ascii_filter":"0x7fffffffffffffffffffffff00000000","#,
+                r#""unicode_block_filter":"0x0","grep_char":null,"scripts":["Latin"],"#,
+                r#""keyword":"cat","keyword_offset":4,"score":null,"language":null,"#,
+                r#""language_confidence":null,"continues_previous":false,"#,
+                r#""string":"the cat"}"#,
+                "\n"
+            )
+        );
+    }
+}
@@ -0,0 +1,207 @@
+//! Keyword search over findings with Aho-Corasick.
+//!
+//! A mission with a keyword list only keeps strings containing at least one
+//! of the keywords. All keywords are searched in one pass, so lists of
+//! thousands of keywords cost little more than a single one. With
+//! `ignore_case`, keywords and strings are compared after Unicode case
+//! folding, e.g. `straße` matches `STRASSE` and `ﬁle` matches `FILE`.
+
+use crate::{case_folding::push_folded, error::Error};
+use aho_corasick::{AhoCorasick, MatchKind};
+use std::{fmt, fs, path::Path, sync::Arc};
+
+/// A keyword list, compiled for searching.
+#[derive(Clone)]
+pub struct Keywords {
+    keywords: Vec<Arc<str>>,
+    ignore_case: bool,
+    automaton: AhoCorasick,
+}
+
+/// The first keyword found in a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeywordMatch {
+    /// The keyword as given in the list.
+    pub keyword: Arc<str>,
+    /// Position of the keyword in the finding's string, counted in
+    /// characters.
+    pub char_offset: usize,
+}
+
+impl Keywords {
+    /// Compiles `keywords`. An empty list, or an empty keyword, which would
+    /// match every string, is an `Error::Keywords`.
+    pub fn new<I, S>(keywords: I, ignore_case: bool) -> crate::Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<Arc<str>>,
+    {
+        let keywords: Vec<Arc<str>> = keywords.into_iter().map(Into::into).collect();
+        if keywords.is_empty() {
+            return Err(Error::Keywords("the list is empty".to_string()));
+        }
+        if keywords.iter().any(|keyword| keyword.is_empty()) {
+            return Err(Error::Keywords("empty keyword".to_string()));
+        }
+        let patterns = keywords.iter().map(|keyword| {
+            if ignore_case {
+                fold_case(keyword).0
+            } else {
+                keyword.to_string()
+            }
+        });
+        let automaton = AhoCorasick::builder()
+            .match_kind(MatchKind::LeftmostFirst)
+            .build(patterns)
+            .map_err(|e| Error::Keywords(e.to_string()))?;
+        Ok(Self {
+            keywords,
+            ignore_case,
+            automaton,
+        })
+    }
+
+    /// Reads a keyword list with one keyword per line. Empty lines are
+    /// skipped, other lines are taken as they are, without the line ending.
+    /// A file without keywords is an `Error::Keywords`.
+    pub fn from_file<P: AsRef<Path>>(path: P, ignore_case: bool) -> crate::Result<Self> {
+        let path = path.as_ref();
+        let list = fs::read_to_string(path)
+            .map_err(|e| Error::KeywordList(path.display().to_string(), e))?;
+        Self::new(
+            list.lines()
+                .map(|line| line.strip_suffix('\r').unwrap_or(line))
+                .filter(|line| !line.is_empty()),
+            ignore_case,
+        )
+    }
+
+    pub fn keywords(&self) -> &[Arc<str>] {
+        &self.keywords
+    }
+
+    pub fn ignore_case(&self) -> bool {
+        self.ignore_case
+    }
+
+    /// Finds the leftmost keyword in `s`. Returns the keyword and its byte
+    /// position in `s`.
+    pub(crate) fn find(&self, s: &str) -> Option<(&Arc<str>, usize)> {
+        if self.ignore_case {
+            let (folded, origins) = fold_case(s);
+            let m = self.automaton.find(&folded)?;
+            Some((&self.keywords[m.pattern()], origins[m.start()]))
+        } else {
+            let m = self.automaton.find(s)?;
+            Some((&self.keywords[m.pattern()], m.start()))
+        }
+    }
+}
+
+impl fmt::Debug for Keywords {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Keywords")
+            .field("len", &self.keywords.len())
+            .field("ignore_case", &self.ignore_case)
+            .finish()
+    }
+}
+
+/// Applies full case folding to `s` character by character. Returns the
+/// folded string, and for every byte of it the byte position in `s` of the
+/// character it was folded from.
+fn fold_case(s: &str) -> (String, Vec<usize>) {
+    let mut folded = String::with_capacity(s.len());
+    let mut origins = Vec::with_capacity(s.len());
+    for (pos, c) in s.char_indices() {
+        let len = folded.len();
+        push_folded(&mut folded, c);
+        origins.resize(origins.len() + folded.len() - len, pos);
+    }
+    (folded, origins)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MissionConfig, StringsScanner, UBF_ALL_VALID};
+    use encoding_rs::UTF_8;
+
+    #[test]
+    fn fold_case_is_full_case_folding() {
+        let cases = [
+            ("FILE", "file"),
+            ("\u{fb01}le", "file"),
+            ("\u{fb03}x", "ffix"),
+            ("Straße", "strasse"),
+            ("STRA\u{1e9e}E", "strasse"),
+            ("ΣΑΣ", "σασ"),
+            ("σας", "σασ"),
+            ("\u{1f88}", "\u{1f00}\u{3b9}"),
+            ("\u{ab70}", "\u{13a0}"),
+            ("\u{13a0}", "\u{13a0}"),
+            ("İ", "i\u{307}"),
+        ];
+        for (s, folded) in cases {
+            assert_eq!(fold_case(s).0, folded, "{}", s);
+        }
+    }
+
+    #[test]
+    fn origins_point_to_the_folded_character() {
+        let (folded, origins) = fold_case("aﬃxß");
+        assert_eq!(folded, "affixss");
+        assert_eq!(origins, [0, 1, 1, 1, 4, 5, 5]);
+
+        let keywords = Keywords::new(["IX", "SS"], true).unwrap();
+        let (keyword, pos) = keywords.find("aﬃxß").unwrap();
+        assert_eq!((&**keyword, pos), ("IX", 1));
+        let (keyword, pos) = keywords.find("Maß").unwrap();
+        assert_eq!((&**keyword, pos), ("SS", 2));
+        assert!(Keywords::new(["IX"], false).unwrap().find("aﬃx").is_none());
+    }
+
+    #[test]
+    fn char_offset_counts_characters_of_the_original_string() {
+        let keywords = Keywords::new(["STRASSE", "file"], true).unwrap();
+        let scanner = StringsScanner::builder()
+            .mission(
+                MissionConfig::new(UTF_8)
+                    .unicode_block_filter(UBF_ALL_VALID)
+                    .keywords(Some(keywords)),
+            )
+            .build()
+            .unwrap();
+        let result = scanner
+            .scan_bytes("\0ÄÖÜ Straße\0\0ﬃﬁle\0\0nothing\0".as_bytes())
+            .unwrap();
+        let matches: Vec<_> = result
+            .findings
+            .iter()
+            .map(|finding| {
+                let keyword_match = finding.keyword_match.as_ref().unwrap();
+                (&*keyword_match.keyword, keyword_match.char_offset)
+            })
+            .collect();
+        assert_eq!(matches, [("STRASSE", 4), ("file", 1)]);
+    }
+
+    #[test]
+    fn empty_keyword_lists_are_rejected() {
+        let none: [&str; 0] = [];
+        assert!(matches!(
+            Keywords::new(none, false),
+            Err(Error::Keywords(_))
+        ));
+        assert!(matches!(
+            Keywords::new(["a", ""], false),
+            Err(Error::Keywords(_))
+        ));
+
+        let path = std::env::temp_dir().join(format!("stringsext-keywords-{}", std::process::id()));
+        fs::write(&path, "\n\r\n\n").unwrap();
+        let result = Keywords::from_file(&path, false);
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::Keywords(_))));
+    }
+}
@@ -0,0 +1,458 @@
+//! Language identification of findings.
+//!
+//! The script of a string often decides the language alone, e.g. Hangul is
+//! Korean, and Han with a share of Kana is Japanese. Where several
+//! languages share a script, the letter trigrams of the string are compared
+//! with built-in profiles: the most frequent trigrams of every language, in
+//! order of frequency. `_` stands for a word boundary in the profiles.
+//! Letters only one language of a script uses, e.g. Ukrainian `ї`, count
+//! like a frequent trigram when telling the languages apart, but not as
+//! evidence of language at all.
+
+use crate::score::{han_chars, hangul_chars, lowercase, Family};
+use std::{collections::HashMap, sync::OnceLock};
+use unicode_script::{Script, UnicodeScript};
+
+/// Strings with fewer letters are not identified.
+const LETTERS_MIN: usize = 3;
+
+/// Share of Kana among Han and Kana letters from which a string is taken
+/// for Japanese. Chinese text quotes a Japanese name now and then.
+const KANA_SHARE_MIN: f32 = 0.1;
+
+/// Share of Kana at which Japanese is certain. Japanese text usually has
+/// more.
+const KANA_SHARE_TEXT: f32 = 0.3;
+
+/// Share of trigrams found in the best profile at which the confidence is
+/// not reduced for lack of evidence.
+const TRIGRAM_RATIO_TEXT: f32 = 0.3;
+
+/// A detected language.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LanguageGuess {
+    /// ISO 639-1 code, e.g. `ru`, `zh`, `ar` or `en`.
+    pub language: &'static str,
+    /// From 0 (a guess) to 1 (certain).
+    pub confidence: f32,
+}
+
+const LATIN_PROFILES: [(&str, &str); 10] = [
+    (
+        "en",
+        "_th|the|he_|and|_an|nd_|_of|of_|_to|ing|ng_|_in|to_|ed_|in_|er_|is_|_is|ion|_a_|es_|ent|\
+         re_|on_|_co|tio|ati|hat|for|_fo|or_|tha|_be|as_|at_|_wi|ter|ly_|all|her|_wh|his|ere|ver|\
+         _re|con|st_|_ha|ith|wit|e_t|s_a|n_t|d_t|e_a|_no|not|you|_yo|ll_",
+    ),
+    (
+        "de",
+        "en_|er_|_de|der|ie_|ich|ein|_di|die|sch|nd_|che|_un|und|den|ch_|_ei|in_|ten|cht|ine|gen|\
+         te_|es_|_da|ung|ng_|nde|_zu|ber|ist|_is|st_|mit|_mi|auf|eit|ver|_ve|ht_|nic|_ni|das|sie|\
+         _si|lic|ier|n_d|sse|ege|_ge|ges|ach|ere|ies|ält|für|_fü|ür_|_wi",
+    ),
+    (
+        "fr",
+        "es_|_de|de_|ent|le_|_le|nt_|ion|la_|_la|re_|e_d|_co|les|on_|tio|ue_|des|_pa|que|_qu|ne_|\
+         e_l|ons|et_|_et|ait|men|s_d|our|_po|par|er_|ur_|_un|une|pas|dan|_da|ans|_en|en_|eme|e_p|\
+         _re|ien|ais|ier|est|_es|te_|_ce|eur|ées|ée_|_à_|ire|ité|té_|au_",
+    ),
+    (
+        "es",
+        "_de|de_|os_|_la|la_|el_|es_|_qu|que|ue_|_el|ent|as_|_en|en_|ion|on_|_co|do_|ado|nte|er_|\
+         cio|ien|los|_lo|_se|ar_|con|_pa|ra_|par|e_l|a_d|o_d|ero|del|est|ada|_es|sta|nto|_un|una|\
+         res|al_|tra|por|_po|ón_|ión|ció|_no|no_|ido|ara|ner|_su",
+    ),
+    (
+        "it",
+        "_di|di_|to_|la_|_la|re_|_de|che|_ch|he_|del|ell|le_|lla|one|ion|ent|no_|_co|ta_|_il|il_|\
+         nte|_pe|per|er_|a_d|o_d|ne_|are|ato|con|in_|_in|i_d|e_d|ere|ra_|ess|zio|tto|tti|ale|ono|\
+         sta|pro|_pr|gli|_gl|non|_no|_un|un_|lo_|_è_|ità|tà_|ett",
+    ),
+    (
+        "pt",
+        "_de|de_|os_|ão_|do_|_do|da_|_da|ent|ar_|_co|que|_qu|ue_|ção|açã|es_|as_|em_|_em|com|nte|\
+         _pa|ra_|o_d|a_d|par|men|est|dos|das|ado|ada|uma|_um|um_|não|_nã|or_|ida|tar|açõ|eme|con|\
+         ser|_se|õe_|ica|_pr|_no|_na|no_|na_|ont|ist|sso|ca_",
+    ),
+    (
+        "nl",
+        "en_|de_|_de|an_|het|_he|et_|van|_va|_ee|een|er_|ver|_ve|aar|ing|nde|ie_|eer|ten|ij_|ijk|\
+         oor|_vo|voo|den|_in|in_|te_|ge_|erd|sch|ste|gen|ens|cht|ond|_on|lij|ee_|eli|nd_|dat|_da|\
+         zij|_zi|ook|nie|_ni|iet|_is|is_|_ge|_me|met|lle|_wo|wor",
+    ),
+    (
+        "pl",
+        "nie|_ni|ie_|ch_|_pr|prz|rze|_po|ego|wie|ani|ych|ia_|_w_|na_|_na|cze|zy_|ost|ać_|em_|ej_|\
+         owa|ki_|_do|dzi|ow_|_je|jes|est|st_|rzy|eni|_si|się|_za|ami|ny_|ów_|ści|ić_|_z_|_to|go_|\
+         ją_|_ja|sta|wa_|_ko|kto|ale|_al|_cz|czy|aln|_mo",
+    ),
+    (
+        "tr",
+        "lar|ler|_bi|bir|ir_|in_|ın_|ara|eri|ini|ar_|er_|an_|en_|ile|_ve|ve_|_ka|ası|asi|len|lan|\
+         nda|ınd|_ol|ola|arı|mak|mek|dır|dir|yor|ıyo|iyo|_de|de_|da_|ına|ine|_bu|bu_|nı_|_ya|ya_|\
+         _ge|ğin|ığı|iği|ama|_ba|ış_|_iç|içi|çin",
+    ),
+    (
+        "id",
+        "an_|ang|_me|kan|_di|ng_|_da|dan|gan|ya_|_ke|nya|men|_pe|ada|_be|ber|_ya|yan|eng|ala|_se|\
+         per|ah_|_ba|ara|in_|_in|ini|apa|_ak|aka|lah|_te|ter|_it|itu|tu_|_ti|tid|ida|dak|ak_|_bi|\
+         san|at_|ran|ata|asi|ama|ena|a_d|a_s|_sa",
+    ),
+];
+
+const CYRILLIC_PROFILES: [(&str, &str); 3] = [
+    (
+        "ru",
+        "_не|не_|_на|на_|ого|ть_|_по|то_|ени|_пр|ост|ст_|ет_|ова|ани|_в_|что|_чт|ия_|го_|ых_|ой_|\
+         ный|ать|ско|ств|про|пре|тор|ров|его|ние|_со|ли_|ся_|ал_|ла_|ил_|ое_|_ко|ред|овы|ель|ом_|\
+         _за|ем_|ые_|_от|ыл_|был|_бы|ься|ому|_эт|это|ты_|её_|_вы",
+    ),
+    (
+        "uk",
+        "_на|на_|_не|не_|ння|ти_|_пр|ого|ні_|_по|ня_|ів_|_за|ист|их_|ий_|ськ|сті|ост|анн|ува|ють|\
+         ати|тьс|що_|_що|і_в|_і_|ова|ько|ькі|ей_|про|ити|він|для|_дл|ої_|_ві|від|ії_|ія_|_у_|\
+         ся_|ть_|_ма|мож|ожн|_бу|бул|_є_|ає_|ує_|_ць|цьо|ьог|ими|ами",
+    ),
+    (
+        "bg",
+        "_на|на_|то_|_за|та_|ите|ата|не_|_не|ни_|ото|_пр|ств|ния|ва_|ост|_по|ето|ст_|ка_|ия_|ли_|\
+         и_с|ени|ане|от_|_от|да_|_да|ски|ско|е_н|ове|при|пре|_съ|със|ъс_|ще_|_ще|ът_|ят_|_тя|тя_|\
+         ви_|_е_|ват|ара|_ка|как|ние|ери|_ко|кой|_че|че_",
+    ),
+];
+
+const ARABIC_PROFILES: [(&str, &str); 2] = [
+    (
+        "ar",
+        "_ال|ال_|الم|ية_|في_|_في|من_|_من|ات_|لى_|على|_عل|ها_|ان_|ين_|الت|ون_|الا|لا_|ما_|يه_|الع|\
+         اء_|الق|الح|هذا|_هذ|ذا_|أن_|_أن|إلى|_إل|كان|_كا|الس|الب|الد|الإ|تي_|الذ|لذي|ذي_|_عا|عن_|\
+         _عن|ولا|_ول|وال|_وا|ت_ا|مة_|كة_|رة_|لة_|_لا|قال|_قا",
+    ),
+    (
+        "fa",
+        "_از|از_|_به|به_|ای_|ها_|_را|را_|که_|_که|ان_|_در|در_|ین_|های|_ای|این|ید_|ست_|است|_اس|ده_|\
+         ود_|شد_|می_|_می|ند_|ری_|ار_|ی_ب|ی_ک|ی_م|یک_|_یک|با_|_با|تر_|کرد|_کر|خود|_خو|برا|_بر|\
+         رای|ران|_آن|آن_|ات_|_ها|هاي|دار|_دا|شود|بود|گی_|ندگ",
+    ),
+];
+
+/// Letters of a script used by only one of the languages profiled for it.
+const MARKER_LETTERS: [(&str, &str); 8] = [
+    ("de", "ß"),
+    ("es", "ñ"),
+    ("pt", "ãõ"),
+    ("pl", "ąęłńśźż"),
+    ("tr", "ığş"),
+    ("ru", "ыэё"),
+    ("uk", "іїєґ"),
+    ("fa", "پچژگ"),
+];
+
+/// Languages decided by the script alone.
+const SCRIPT_LANGUAGES: [(Script, &str); 17] = [
+    (Script::Greek, "el"),
+    (Script::Hebrew, "he"),
+    (Script::Armenian, "hy"),
+    (Script::Georgian, "ka"),
+    (Script::Devanagari, "hi"),
+    (Script::Bengali, "bn"),
+    (Script::Gurmukhi, "pa"),
+    (Script::Gujarati, "gu"),
+    (Script::Tamil, "ta"),
+    (Script::Telugu, "te"),
+    (Script::Kannada, "kn"),
+    (Script::Malayalam, "ml"),
+    (Script::Thai, "th"),
+    (Script::Lao, "lo"),
+    (Script::Khmer, "km"),
+    (Script::Sinhala, "si"),
+    (Script::Ethiopic, "am"),
+];
+
+/// Detects the language of `s`. Returns `None` for strings with too few
+/// letters, or with letters of a script without a known language.
+pub fn detect_language(s: &str) -> Option<LanguageGuess> {
+    let mut letters_by_family: Vec<(Family, usize)> = Vec::new();
+    let mut kana = 0usize;
+    let mut frequent = 0usize;
+    for c in s.chars() {
+        let Some(family) = Family::of(c) else {
+            continue;
+        };
+        match letters_by_family.iter_mut().find(|(f, _)| *f == family) {
+            Some((_, n)) => *n += 1,
+            None => letters_by_family.push((family, 1)),
+        }
+        match c.script() {
+            Script::Hiragana | Script::Katakana => kana += 1,
+            Script::Han if han_chars().contains(&c) => frequent += 1,
+            Script::Hangul if hangul_chars().contains(&c) => frequent += 1,
+            _ => {}
+        }
+    }
+    let letters: usize = letters_by_family.iter().map(|(_, n)| n).sum();
+    let &(family, dominant) = letters_by_family.iter().max_by_key(|(_, n)| *n)?;
+    if letters < LETTERS_MIN {
+        return None;
+    }
+    let share = dominant as f32 / letters as f32;
+    let guess = |language, confidence: f32| {
+        Some(LanguageGuess {
+            language,
+            confidence: (share * confidence).clamp(0.0, 1.0),
+        })
+    };
+    match family {
+        Family::Han if kana as f32 >= KANA_SHARE_MIN * dominant as f32 => {
+            guess("ja", kana as f32 / dominant as f32 / KANA_SHARE_TEXT)
+        }
+        Family::Han => guess("zh", frequent as f32 / dominant as f32 / 0.6),
+        Family::Hangul => guess("ko", frequent as f32 / dominant as f32 / 0.6),
+        Family::Greek => guess("el", 1.0),
+        Family::Other(script) => SCRIPT_LANGUAGES
+            .iter()
+            .find(|(s, _)| *s == script)
+            .and_then(|(_, language)| guess(language, 1.0)),
+        Family::Latin | Family::Cyrillic | Family::Arabic => {
+            let (language, confidence) = compare_trigrams(s, family)?;
+            guess(language, confidence)
+        }
+    }
+}
+
+/// A trigram profile: the weight of every trigram, from 1 for the most
+/// frequent one down to almost 0.
+struct Profile {
+    language: &'static str,
+    weights: HashMap<[char; 3], f32>,
+    markers: &'static str,
+}
+
+fn profiles(family: Family) -> &'static [Profile] {
+    static LATIN: OnceLock<Vec<Profile>> = OnceLock::new();
+    static CYRILLIC: OnceLock<Vec<Profile>> = OnceLock::new();
+    static ARABIC: OnceLock<Vec<Profile>> = OnceLock::new();
+    let (cell, table): (_, &[(&str, &str)]) = match family {
+        Family::Latin => (&LATIN, &LATIN_PROFILES),
+        Family::Cyrillic => (&CYRILLIC, &CYRILLIC_PROFILES),
+        Family::Arabic => (&ARABIC, &ARABIC_PROFILES),
+        _ => return &[],
+    };
+    cell.get_or_init(|| {
+        table
+            .iter()
+            .map(|&(language, trigrams)| {
+                let trigrams: Vec<[char; 3]> = trigrams
+                    .split('|')
+                    .filter_map(|trigram| {
+                        let chars: Vec<char> = trigram
+                            .chars()
+                            .map(|c| if c == '_' { ' ' } else { c })
+                            .collect();
+                        chars.try_into().ok()
+                    })
+                    .collect();
+                let mut weights = HashMap::new();
+                for (rank, trigram) in trigrams.iter().enumerate() {
+                    weights
+                        .entry(*trigram)
+                        .or_insert(1.0 - rank as f32 / trigrams.len() as f32);
+                }
+                let markers = MARKER_LETTERS
+                    .iter()
+                    .find(|(l, _)| *l == language)
+                    .map_or("", |(_, markers)| markers);
+                Profile {
+                    language,
+                    weights,
+                    markers,
+                }
+            })
+            .collect()
+    })
+}
+
+/// Finds the profile of `family` that matches the trigrams of `s` best.
+/// Returns its language and a confidence, which is low when another
+/// profile matches almost as well, or when few trigrams match at all.
+fn compare_trigrams(s: &str, family: Family) -> Option<(&'static str, f32)> {
+    // Letters of `family` in lower case, everything else is a word boundary.
+    let mut text = vec![' '];
+    for c in s.chars() {
+        if Family::of(c) == Some(family) {
+            text.push(lowercase(c));
+        } else if text.last() != Some(&' ') {
+            text.push(' ');
+        }
+    }
+    if text.last() != Some(&' ') {
+        text.push(' ');
+    }
+    let trigrams: Vec<[char; 3]> = text.windows(3).map(|w| [w[0], w[1], w[2]]).collect();
+    if trigrams.is_empty() {
+        return None;
+    }
+    // The language, its score with markers, and without.
+    let mut scores: Vec<(&'static str, f32, f32)> = profiles(family)
+        .iter()
+        .map(|profile| {
+            let trigram_score = trigrams
+                .iter()
+                .filter_map(|trigram| profile.weights.get(trigram))
+                .sum::<f32>()
+                / trigrams.len() as f32;
+            let markers = text
+                .iter()
+                .filter(|c| profile.markers.contains(**c))
+                .count() as f32;
+            (
+                profile.language,
+                trigram_score + markers / trigrams.len() as f32,
+                trigram_score,
+            )
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    let (language, best, best_trigram_score) = *scores.first()?;
+    if best_trigram_score == 0.0 {
+        return None;
+    }
+    let second = scores.get(1).map_or(0.0, |(_, score, _)| *score);
+    let margin = (best - second) / best;
+    // Markers tell languages apart, but random letters hit them, too.
+    let evidence = (best_trigram_score / TRIGRAM_RATIO_TEXT).min(1.0);
+    Some((language, (0.5 + 0.5 * margin) * evidence))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// One sentence gives little evidence, but noise gives less.
+    const CONFIDENCE_TEXT: f32 = 0.2;
+
+    #[test]
+    fn one_sentence_per_language() {
+        let sentences = [
+            (
+                "en",
+                "The children went to the park with their mother in the morning.",
+            ),
+            (
+                "de",
+                "Die Kinder sind mit ihrer Mutter in den Park gegangen, weil es schön war.",
+            ),
+            (
+                "fr",
+                "Les enfants sont allés au parc avec leur mère pendant les vacances.",
+            ),
+            (
+                "es",
+                "Los niños fueron al parque con su madre porque hacía buen tiempo.",
+            ),
+            (
+                "it",
+                "I bambini sono andati al parco con la loro madre per giocare.",
+            ),
+            (
+                "pt",
+                "As crianças foram ao parque com a mãe porque não havia aulas.",
+            ),
+            (
+                "nl",
+                "De kinderen zijn met hun moeder naar het park gegaan omdat het mooi weer was.",
+            ),
+            (
+                "pl",
+                "Dzieci poszły z matką do parku, ponieważ była piękna pogoda.",
+            ),
+            (
+                "tr",
+                "Çocuklar annesiyle birlikte parka gittiler çünkü hava çok güzeldi.",
+            ),
+            (
+                "id",
+                "Anak-anak pergi ke taman dengan ibu mereka karena cuacanya sangat bagus.",
+            ),
+            (
+                "ru",
+                "Дети пошли в парк со своей матерью, потому что была хорошая погода.",
+            ),
+            (
+                "uk",
+                "Діти пішли до парку разом з матір'ю, тому що була дуже гарна погода.",
+            ),
+            (
+                "bg",
+                "Децата отидоха в парка с майка си, защото времето беше хубаво.",
+            ),
+            ("ar", "ذهب الأطفال إلى الحديقة مع والدتهم لأن الطقس كان جميلا."),
+            ("fa", "بچه‌ها با مادرشان به پارک رفتند چون هوا خیلی خوب بود."),
+            ("el", "Τα παιδιά πήγαν στο πάρκο με τη μητέρα τους."),
+            ("ja", "子供たちは母親と一緒に公園へ行きました。"),
+            ("zh", "孩子们和他们的母亲一起去了公园。"),
+            (
+                "ko",
+                "아이들은 날씨가 좋아서 어머니와 함께 공원에 갔습니다.",
+            ),
+        ];
+        for (language, sentence) in sentences {
+            let guess = detect_language(sentence).unwrap();
+            assert_eq!(guess.language, language, "{}", sentence);
+            assert!(
+                guess.confidence >= CONFIDENCE_TEXT,
+                "{}: {:?}",
+                sentence,
+                guess
+            );
+        }
+    }
+
+    /// Ten words of random Cyrillic letters, like windows-1251 decoded from
+    /// binary data.
+    fn cyrillic_noise(seed: u64) -> String {
+        let mut state = seed;
+        (0..60)
+            .map(|i| {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                match i % 6 {
+                    5 => ' ',
+                    _ => char::from_u32(0x430 + (state % 32) as u32).unwrap(),
+                }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn noise_is_not_identified() {
+        let noise = [
+            "",
+            "x1",
+            "Qx7#kPz!wQ9vJ",
+            "zqxjkv wpfgh btrmd",
+            "ЖЩЪЭЮЯ ЬЫЩЖ",
+            "擘灪鼈纛龘 鱻",
+        ];
+        let cyrillic = (1..=100).map(cyrillic_noise);
+        for s in noise.into_iter().map(String::from).chain(cyrillic) {
+            let guess = detect_language(&s);
+            assert!(
+                guess.is_none_or(|guess| guess.confidence < CONFIDENCE_TEXT),
+                "{}: {:?}",
+                s,
+                guess
+            );
+        }
+    }
+
+    #[test]
+    fn a_single_kana_does_not_make_chinese_japanese() {
+        let guess = detect_language("我们在这个国家的大学里学习中文和历史の").unwrap();
+        assert_eq!(guess.language, "zh");
+        let guess = detect_language("東京都の人口").unwrap();
+        assert_eq!(guess.language, "ja");
+    }
+}
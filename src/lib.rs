@@ -1,30 +1,236 @@
 #![allow(clippy::too_many_arguments)]
 
+#[cfg(feature = "async")]
+mod async_scan;
+mod case_folding;
+mod delimited;
 pub mod error;
+mod filter;
 mod finding;
 mod finding_collection;
+mod group_filter;
 mod helper;
 mod input;
+#[cfg(feature = "serde")]
+mod json;
+mod keywords;
+mod language;
 mod mission;
+mod names;
+mod needle;
 mod options;
 mod scanner;
+mod scheduler;
+mod score;
+mod script;
+mod text;
 
-use crate::{finding_collection::FindingCollection, input::Slicer, scanner::ScannerStates};
+use crate::{input::INPUT_BUF_LEN, options::SEGMENT_LEN_DEFAULT};
 use mission::Missions;
-use scoped_threadpool::Pool;
-use std::{
-    path::PathBuf,
-    pin::Pin,
-    sync::{mpsc, Arc, Mutex},
+
+pub use crate::{
+    delimited::DelimitedWriter,
+    filter::{parse_ascii_filter, parse_unicode_block_filter},
+    finding::{OwnedFinding, Precision, ScanResult, ScanSummary},
+    input::{ByteCounter, FailedInput, Input, InputFileId, InputLabel},
+    keywords::{KeywordMatch, Keywords},
+    language::{detect_language, LanguageGuess},
+    mission::{
+        scanner_label, CliOptions, Mission, MissionConfig, MissionId, Utf8Filter, AF_ALL, AF_CTRL,
+        AF_DEFAULT, AF_NONE, AF_WHITESPACE, UBF_ACCENTS, UBF_AFRICAN, UBF_ALL, UBF_ALL_VALID,
+        UBF_ARABIC, UBF_ARMENIAN, UBF_ASIAN, UBF_CJK, UBF_COMMON, UBF_CYRILLIC, UBF_GREEK,
+        UBF_HANGUL, UBF_HEBREW, UBF_INVALID, UBF_IPA, UBF_KANA, UBF_LATIN, UBF_MISC, UBF_NONE,
+        UBF_PUA, UBF_SYRIAC, UBF_UNCOMMON, UTF8_FILTER_ASCII_MODE_DEFAULT,
+        UTF8_FILTER_NON_ASCII_MODE_DEFAULT,
+    },
+    names::{
+        ascii_filter_aliases, encodings, unicode_block_filter_aliases, EncodingLabels, FilterAlias,
+    },
+    needle::NeedleHit,
+    options::{OnInputError, Radix},
+    score::plausibility,
+    script::{Script, ScriptFilter},
+    text::TextWriter,
 };
+#[cfg(feature = "async")]
+pub use async_scan::AsyncScan;
+pub use encoding_rs;
+use encoding_rs::ISO_2022_JP;
+#[cfg(feature = "serde")]
+pub use json::{JsonLinesWriter, JSON_SCHEMA_VERSION};
+use std::thread;
 
 pub type Result<T> = std::result::Result<T, error::Error>;
 
 pub struct StringsScanner {
-    scaner_states: ScannerStates,
+    missions: Missions,
+    chunk_size: usize,
+    workers: usize,
+    segment_len: Option<usize>,
+    on_input_error: OnInputError,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+}
+
+/// Typed alternative to the string based `StringsScanner::new()`.
+#[derive(Debug, Clone)]
+pub struct ScannerBuilder {
+    missions: Vec<MissionConfig>,
+    cli_options: CliOptions,
+    chunk_size: usize,
+    workers: usize,
+    segment_len: Option<usize>,
+    on_input_error: OnInputError,
+    #[cfg(feature = "mmap")]
+    mmap: bool,
+}
+
+impl Default for ScannerBuilder {
+    fn default() -> Self {
+        Self {
+            missions: Vec::new(),
+            cli_options: CliOptions::default(),
+            chunk_size: INPUT_BUF_LEN,
+            workers: thread::available_parallelism().map_or(1, |n| n.get()),
+            segment_len: Some(SEGMENT_LEN_DEFAULT),
+            on_input_error: OnInputError::default(),
+            #[cfg(feature = "mmap")]
+            mmap: false,
+        }
+    }
+}
+
+impl ScannerBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a mission. Every mission is scanned in its own thread. Without
+    /// any mission, one default UTF-8 mission is created.
+    pub fn mission(mut self, mission: MissionConfig) -> Self {
+        self.missions.push(mission);
+        self
+    }
+
+    pub fn missions<I: IntoIterator<Item = MissionConfig>>(mut self, missions: I) -> Self {
+        self.missions.extend(missions);
+        self
+    }
+
+    /// Size of the slices the input is cut into. Every slice is scanned by
+    /// all missions before the next one is started.
+    pub fn chunk_size(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
+
+    /// Number of worker threads scanning inputs, or segments of inputs, in
+    /// parallel. Defaults to the available parallelism. With several
+    /// missions, every worker runs one additional thread per mission.
+    pub fn workers(mut self, workers: usize) -> Self {
+        self.workers = workers;
+        self
+    }
+
+    /// In-memory and memory-mapped inputs larger than `segment_len` bytes
+    /// are cut into segments, which are scanned in parallel. Inputs are
+    /// only cut where this does not change the result, i.e. between runs
+    /// of zero bytes. `None` disables segmenting. `build()` fails with
+    /// `Error::SegmentLen` for lengths below 4 bytes, the zeros needed on
+    /// each side of a cut.
+    pub fn segment_len(mut self, segment_len: Option<usize>) -> Self {
+        self.segment_len = segment_len;
+        self
+    }
+
+    /// Whether an input that can not be opened or read aborts the scan,
+    /// which is the default, or is skipped and reported in the result.
+    pub fn on_input_error(mut self, on_input_error: OnInputError) -> Self {
+        self.on_input_error = on_input_error;
+        self
+    }
+
+    /// Memory-maps input files instead of reading them. The scanner threads
+    /// then work directly on slices of the mapped file.
+    ///
+    /// The files must not be modified while they are scanned. Changes made
+    /// by other processes show up in the scan, and when a file is truncated,
+    /// the process is killed by SIGBUS on reading past its new end.
+    #[cfg(feature = "mmap")]
+    pub fn mmap(mut self, mmap: bool) -> Self {
+        self.mmap = mmap;
+        self
+    }
+
+    /// Command line style options for all missions built by
+    /// `build_from_args()`, e.g. `--regex` or `--keywords`.
+    pub fn cli_options(mut self, cli_options: CliOptions) -> Self {
+        self.cli_options = cli_options;
+        self
+    }
+
+    /// Builds the scanner with missions parsed from command line style
+    /// strings, see `StringsScanner::new()`, and `cli_options()`. Missions
+    /// added with `mission()` are ignored.
+    pub fn build_from_args(
+        self,
+        counter_offset: Option<&String>,
+        encodings: &[String],
+        chars_min: Option<&String>,
+        same_unicode_block: bool,
+        ascii_filter: Option<&String>,
+        unicode_block_filter: Option<&String>,
+        grep_char: Option<&String>,
+        output_line_len: Option<&String>,
+    ) -> Result<StringsScanner> {
+        let configs = Missions::parse_configs(
+            counter_offset,
+            encodings,
+            chars_min,
+            same_unicode_block,
+            ascii_filter,
+            unicode_block_filter,
+            grep_char,
+            output_line_len,
+        )?;
+        let missions = Missions::from_configs(self.cli_options.apply(configs)?)?;
+        self.finish(missions)
+    }
+
+    pub fn build(mut self) -> Result<StringsScanner> {
+        let missions = Missions::from_configs(std::mem::take(&mut self.missions))?;
+        self.finish(missions)
+    }
+
+    fn finish(self, missions: Missions) -> Result<StringsScanner> {
+        if self.chunk_size == 0 {
+            return Err(error::Error::ChunkSize(self.chunk_size));
+        }
+        if self.workers == 0 {
+            return Err(error::Error::Workers(self.workers));
+        }
+        if let Some(segment_len) = self.segment_len {
+            if segment_len < scheduler::SEGMENT_BOUNDARY_ZEROS {
+                return Err(error::Error::SegmentLen(segment_len));
+            }
+        }
+        Ok(StringsScanner {
+            missions,
+            chunk_size: self.chunk_size,
+            workers: self.workers,
+            segment_len: self.segment_len,
+            on_input_error: self.on_input_error,
+            #[cfg(feature = "mmap")]
+            mmap: self.mmap,
+        })
+    }
 }
 
 impl StringsScanner {
+    pub fn builder() -> ScannerBuilder {
+        ScannerBuilder::new()
+    }
+
     pub fn new(
         counter_offset: Option<&String>,
         encodings: &[String],
@@ -35,7 +241,7 @@ impl StringsScanner {
         grep_char: Option<&String>,
         output_line_len: Option<&String>,
     ) -> Result<Self> {
-        let missions = Missions::new(
+        ScannerBuilder::new().build_from_args(
             counter_offset,
             encodings,
             chars_min,
@@ -44,73 +250,384 @@ impl StringsScanner {
             unicode_block_filter,
             grep_char,
             output_line_len,
-        )?;
-        let scaner_states = ScannerStates::new(missions);
-        Ok(Self { scaner_states })
-    }
-
-    #[allow(unused_assignments)]
-    pub fn run(&mut self, paths: Vec<PathBuf>) -> Result<Vec<String>> {
-        let merger;
-        let buf = Arc::new(Mutex::new(Vec::new()));
-        let buff = buf.clone();
-        let n_threads = self.scaner_states.len();
-        {
-            let (tx, rx) = mpsc::sync_channel::<Pin<Box<FindingCollection>>>(n_threads);
-            merger = std::thread::spawn(move || {
-                //                let mut output = Box::new(io::stdout()) as Box<dyn Write>;
-                buf.lock().unwrap().push("\u{feff}".to_string());
-                let mut stop = false;
-                'batch_receiver: loop {
-                    let mut results: Vec<Pin<Box<FindingCollection>>> =
-                        Vec::with_capacity(n_threads);
-                    for _ in 0..n_threads {
-                        results.push(match rx.recv() {
-                            Ok(fc) => fc,
-                            _ => {
-                                stop = true;
-                                break 'batch_receiver;
-                            }
-                        });
-                    }
-                    let mut mm = buf.lock().unwrap();
-                    //                    let mmm = mm.get_mut();
-                    for finding in itertools::kmerge(&results) {
-                        mm.push(finding.s.to_string());
-                        //                        finding.print(mmm)?;
-                    }
-                    if stop {
-                        break;
-                    }
-                }
-                //                buf.lock().unwrap().write_all(&[b'\n'])?;
-                //                buf.lock().unwrap().flush()?;
-                Ok::<_, error::Error>(())
+        )
+    }
+
+    /// A `TextWriter` that writes offsets in `radix`, if any, the scanner
+    /// label and encoding when there are several missions, the
+    /// matched keywords when a mission has a keyword list, and the language
+    /// when a mission detects it.
+    pub fn text_writer<W: std::io::Write>(&self, out: W, radix: Option<Radix>) -> TextWriter<W> {
+        TextWriter::new(out)
+            .radix(radix)
+            .mission_column(self.missions.len() > 1)
+            .keyword_column(self.missions.iter().any(|m| m.keywords.is_some()))
+            .language_column(self.detects_language())
+    }
+
+    /// Does any mission detect languages? Then writers need a language
+    /// column, e.g. `DelimitedWriter::language_column()`.
+    pub fn detects_language(&self) -> bool {
+        self.missions.iter().any(|m| m.detect_language)
+    }
+
+    /// Scans `inputs` and collects all findings in input order. Inputs are
+    /// anything convertible into `Input`, e.g. `PathBuf`s or byte slices.
+    pub fn run<'a, I>(&self, inputs: I) -> Result<ScanResult>
+    where
+        I: IntoIterator,
+        I::Item: Into<Input<'a>>,
+    {
+        let mut findings = Vec::new();
+        let summary = self.run_with(inputs, |finding| {
+            findings.push(finding);
+            Ok(())
+        })?;
+        Ok(ScanResult {
+            findings,
+            failed_inputs: summary.failed_inputs,
+        })
+    }
+
+    /// Scans `inputs` and hands every finding over to `f` as soon as the
+    /// input chunk it was found in has been processed by all missions.
+    /// Findings arrive in the same order as with `run()`: input by input,
+    /// and within an input by position. Positions are counted from the start
+    /// of each input.
+    ///
+    /// Reading is throttled when `f` can not keep up, so memory usage does
+    /// not grow with the input size. Missions filtering strings, e.g. with
+    /// `MissionConfig::regex()`, hold back the parts of a string and all
+    /// later findings until the string ends, but at most 1 MiB of it, see
+    /// `MissionConfig::regex()`. When `f` returns an error, the scan is
+    /// stopped and the error is returned.
+    ///
+    /// An input that can not be opened or read ends the scan with
+    /// `Error::Input`, unless the scanner was built with
+    /// `OnInputError::Continue`. Then the failed inputs are listed in the
+    /// returned summary.
+    pub fn run_with<'a, I, F>(&self, inputs: I, f: F) -> Result<ScanSummary>
+    where
+        I: IntoIterator,
+        I::Item: Into<Input<'a>>,
+        F: FnMut(OwnedFinding) -> Result<()>,
+    {
+        self.scan(inputs.into_iter().map(Into::into).collect(), f)
+    }
+
+    /// Scans a buffer that is already in memory.
+    pub fn scan_bytes(&self, bytes: &[u8]) -> Result<ScanResult> {
+        self.scan_many(&[bytes])
+    }
+
+    /// Scans several in-memory buffers, one after the other, as if they were
+    /// input files. `OwnedFinding::input_file_id` counts the buffers from 1.
+    pub fn scan_many(&self, buffers: &[&[u8]]) -> Result<ScanResult> {
+        self.run(buffers.iter().map(|bytes| Input::Bytes(bytes)))
+    }
+
+    /// Returns a `Stream` of the findings in `reader`. The CPU work is done
+    /// on the polling task, chunk by chunk, so many inputs can be scanned
+    /// concurrently without a blocking thread each.
+    #[cfg(feature = "async")]
+    pub fn scan_async<R>(&self, reader: R) -> AsyncScan<R>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+    {
+        AsyncScan::new(reader, &self.missions, self.chunk_size)
+    }
+
+    /// Searches `needle` in the raw bytes of `inputs`, encoded in the
+    /// encoding of every mission and in UTF-16LE, UTF-16BE and
+    /// x-user-defined, and hands every hit over to `f`. Unlike
+    /// `run_with()`, this does not decode the inputs, and ignores the
+    /// missions' filters. Hits arrive input by input, and within an input in
+    /// the order they end. Encodings that can not represent `needle` are
+    /// skipped.
+    ///
+    /// Inputs that can not be opened or read are handled as with
+    /// `run_with()`.
+    pub fn find_needle<'a, I, F>(&self, needle: &str, inputs: I, f: F) -> Result<ScanSummary>
+    where
+        I: IntoIterator,
+        I::Item: Into<Input<'a>>,
+        F: FnMut(NeedleHit) -> Result<()>,
+    {
+        let needle = needle::Needle::new(needle, &self.missions)?;
+        self.with_mapped_inputs(inputs.into_iter().map(Into::into).collect(), |inputs| {
+            needle.search(inputs, self.chunk_size, self.on_input_error, f)
+        })
+    }
+
+    fn scan<F>(&self, inputs: Vec<Input>, f: F) -> Result<ScanSummary>
+    where
+        F: FnMut(OwnedFinding) -> Result<()>,
+    {
+        self.with_mapped_inputs(inputs, |inputs| self.scan_inputs(inputs, f))
+    }
+
+    /// Calls `g` with the labeled `inputs`, the files memory-mapped when the
+    /// scanner was built with `mmap`.
+    fn with_mapped_inputs<T, G>(&self, inputs: Vec<Input>, g: G) -> T
+    where
+        G: FnOnce(Vec<(InputLabel, Input)>) -> T,
+    {
+        let inputs = Input::label_all(inputs);
+        #[cfg(feature = "mmap")]
+        if self.mmap {
+            let maps = input::map_files(&inputs);
+            let inputs = inputs
+                .into_iter()
+                .zip(&maps)
+                .map(|((input_label, input), map)| match map {
+                    Some(map) => (input_label, Input::Bytes(map)),
+                    None => (input_label, input),
+                })
+                .collect();
+            return g(inputs);
+        }
+        g(inputs)
+    }
+
+    fn scan_inputs<F>(&self, inputs: Vec<(InputLabel, Input)>, f: F) -> Result<ScanSummary>
+    where
+        F: FnMut(OwnedFinding) -> Result<()>,
+    {
+        // The ISO-2022-JP decoder keeps its state across zero bytes, so we
+        // can not cut its input into independent segments.
+        let segment_len = self
+            .segment_len
+            .filter(|_| self.missions.iter().all(|m| m.encoding != ISO_2022_JP));
+        let jobs = scheduler::plan(inputs, segment_len, self.chunk_size);
+        scheduler::run(
+            jobs,
+            &self.missions,
+            self.chunk_size,
+            self.workers,
+            self.on_input_error,
+            f,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::INPUT_BUF_LEN;
+
+    fn strings(bytes: &[u8]) -> Vec<String> {
+        let scanner = StringsScanner::builder().build().unwrap();
+        let result = scanner.scan_bytes(bytes).unwrap();
+        result
+            .findings
+            .into_iter()
+            .map(|finding| finding.s)
+            .collect()
+    }
+
+    #[test]
+    fn string_ending_at_eof_is_reported() {
+        assert_eq!(strings(b"\0\0hello world"), ["hello world"]);
+    }
+
+    #[test]
+    fn string_ending_at_eof_on_a_chunk_boundary_is_reported() {
+        let mut bytes = vec![0u8; 2 * INPUT_BUF_LEN - 12];
+        bytes.extend_from_slice(b"hello world!");
+        assert_eq!(bytes.len() % INPUT_BUF_LEN, 0);
+        assert_eq!(strings(&bytes), ["hello world!"]);
+    }
+
+    #[test]
+    fn iso_2022_jp_inputs_are_not_segmented() {
+        // The decoder stays in JIS X 0208 mode across the zeros.
+        let mut bytes = b"\x1b$B".to_vec();
+        for _ in 0..16 {
+            bytes.extend_from_slice(b"F|K\\8l");
+            bytes.extend_from_slice(&[0; 26]);
+        }
+        let scan = |segment_len| {
+            let scanner = StringsScanner::builder()
+                .mission(
+                    MissionConfig::new(ISO_2022_JP)
+                        .chars_min(3)
+                        .unicode_block_filter(UBF_ALL),
+                )
+                .chunk_size(16)
+                .segment_len(segment_len)
+                .workers(4)
+                .build()
+                .unwrap();
+            let result = scanner.scan_bytes(&bytes).unwrap();
+            result
+                .findings
+                .into_iter()
+                .map(|finding| (finding.position, finding.s))
+                .collect::<Vec<_>>()
+        };
+        let expected = scan(None);
+        assert_eq!(expected.len(), 16);
+        assert!(expected.iter().all(|(_, s)| s == "日本語"));
+        assert_eq!(scan(Some(16)), expected);
+    }
+
+    #[cfg(feature = "mmap")]
+    #[test]
+    fn mapped_files_keep_their_labels() {
+        use std::{fs, path::PathBuf};
+
+        let path = std::env::temp_dir().join(format!("stringsext-mmap-{}", std::process::id()));
+        fs::write(&path, b"\0\0hello world\0").unwrap();
+        let scanner = StringsScanner::builder().mmap(true).build().unwrap();
+        let inputs = || {
+            [
+                Input::Path(path.clone()),
+                Input::Path(path.clone()).labeled("x"),
+            ]
+        };
+        let result = scanner.run(inputs());
+        let mut labels = Vec::new();
+        let needle = scanner.find_needle("world", inputs(), |hit| {
+            labels.push((*hit.input_label).clone());
+            Ok(())
+        });
+        fs::remove_file(&path).unwrap();
+        let expected = [
+            InputLabel::Path(PathBuf::from(&path)),
+            InputLabel::Label("x".to_string()),
+        ];
+        let findings = result.unwrap().findings;
+        assert_eq!(findings.len(), 2);
+        for (finding, expected) in findings.iter().zip(&expected) {
+            assert_eq!(finding.s, "hello world");
+            assert_eq!(*finding.input_label, *expected);
+        }
+        needle.unwrap();
+        assert_eq!(labels, expected);
+    }
+
+    /// Fails on every read.
+    struct FailingReader;
+
+    impl std::io::Read for FailingReader {
+        fn read(&mut self, _buf: &mut [u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::other("device gone"))
+        }
+    }
+
+    #[test]
+    fn input_errors_abort_or_are_recorded() {
+        use std::{io::Read, path::PathBuf};
+
+        let missing = PathBuf::from("/nonexistent/stringsext-input");
+        let mut before = b"\0\0before\0".to_vec();
+        before.resize(2 * INPUT_BUF_LEN + 3, 0);
+        before.extend_from_slice(b"cut");
+        let inputs = || {
+            [
+                Input::Bytes(b"\0\0first\0"),
+                Input::Path(missing.clone()),
+                Input::reader(before.as_slice().chain(FailingReader)),
+                Input::Bytes(b"\0\0last\0"),
+            ]
+        };
+        let scan = |on_input_error| {
+            let scanner = StringsScanner::builder()
+                .on_input_error(on_input_error)
+                .build()
+                .unwrap();
+            let mut strings = Vec::new();
+            let summary = scanner.run_with(inputs(), |finding| {
+                strings.push(finding.s);
+                Ok(())
             });
+            (strings, summary)
+        };
 
-            let input = Slicer::new(paths);
-            let mut pool = Pool::new(self.scaner_states.len() as u32);
-            for (slice, input_file_id, is_last_input_buffer) in input {
-                pool.scoped(|scope| {
-                    for ss in self.scaner_states.v.iter() {
-                        let tx = tx.clone();
-                        let slice = slice.clone();
-                        scope.execute(move || {
-                            let fc = FindingCollection::from(
-                                ss.clone(),
-                                input_file_id,
-                                &slice,
-                                is_last_input_buffer,
-                            );
-                            tx.send(fc).unwrap();
-                        });
-                    }
-                });
-            }
+        let (strings, summary) = scan(OnInputError::Abort);
+        assert_eq!(strings, ["first"]);
+        assert!(matches!(
+            summary,
+            Err(error::Error::Input(InputLabel::Path(path), _)) if path == missing
+        ));
+
+        let (strings, summary) = scan(OnInputError::Continue);
+        // The string cut by the error is lost, the ones before it are kept.
+        assert_eq!(strings, ["first", "before", "last"]);
+        let failed: Vec<_> = summary
+            .unwrap()
+            .failed_inputs
+            .into_iter()
+            .map(|failed| (failed.input_file_id, (*failed.input_label).clone()))
+            .collect();
+        assert_eq!(
+            failed,
+            [
+                (2, InputLabel::Path(missing.clone())),
+                (3, InputLabel::Label("#3".to_string())),
+            ]
+        );
+    }
+
+    #[test]
+    fn segment_len_edge_values() {
+        let build = |segment_len, chunk_size| {
+            StringsScanner::builder()
+                .segment_len(segment_len)
+                .chunk_size(chunk_size)
+                .workers(2)
+                .build()
+        };
+        assert!(matches!(
+            build(Some(0), 1),
+            Err(error::Error::SegmentLen(0))
+        ));
+        assert!(matches!(
+            build(Some(3), 1),
+            Err(error::Error::SegmentLen(3))
+        ));
+        let mut bytes = [0u8; 64];
+        bytes[40..45].copy_from_slice(b"hello");
+        for (segment_len, chunk_size) in [(4, 1), (4, 3), (usize::MAX, 1)] {
+            let scanner = build(Some(segment_len), chunk_size).unwrap();
+            let findings = scanner.scan_bytes(&bytes).unwrap().findings;
+            let strings: Vec<_> = findings.into_iter().map(|finding| finding.s).collect();
+            assert_eq!(strings, ["hello"]);
         }
-        merger.join().unwrap()?;
-        let ll = buff.lock().unwrap();
-        //        let lll = ll.get_mut().drain(..);
-        Ok(ll.to_vec())
+    }
+
+    #[test]
+    fn language_field_selects_missions() {
+        let encodings = ["UTF-8,,,,,,,lang".to_string(), "ascii".to_string()];
+        let build = |cli_options| {
+            ScannerBuilder::new()
+                .cli_options(cli_options)
+                .build_from_args(None, &encodings, None, false, None, None, None, None)
+                .unwrap()
+        };
+        let scanner = build(CliOptions::default());
+        let detects: Vec<_> = scanner.missions.iter().map(|m| m.detect_language).collect();
+        assert_eq!(detects, [true, false]);
+        assert!(scanner.detects_language());
+
+        let scanner = build(CliOptions {
+            detect_language: true,
+            ..CliOptions::default()
+        });
+        assert!(scanner.missions.iter().all(|m| m.detect_language));
+
+        let error = ScannerBuilder::new()
+            .build_from_args(
+                None,
+                &["UTF-8,,,,,,,language".to_string()],
+                None,
+                false,
+                None,
+                None,
+                None,
+                None,
+            )
+            .err();
+        assert!(matches!(error, Some(error::Error::LanguageField(_))));
     }
 }
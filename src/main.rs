@@ -0,0 +1,345 @@
+//! The `stringsext` command line tool.
+
+use clap::{Parser, ValueEnum};
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    process::ExitCode,
+};
+use stringexts::{
+    error::Error, CliOptions, DelimitedWriter, Input, OnInputError, OwnedFinding, Radix,
+    ScanSummary, ScannerBuilder, StringsScanner, TextWriter,
+};
+
+/// At least one input could not be read, or the output could not be
+/// written.
+const EXIT_FAILURE: u8 = 1;
+/// Invalid arguments. `clap` uses the same code for usage errors.
+const EXIT_USAGE: u8 = 2;
+
+/// Find multi-byte encoded strings in binary data.
+///
+/// Prints all sequences of printable characters in FILEs, or stdin, that
+/// are at least MIN characters long, in every given encoding.
+#[derive(Parser)]
+#[command(
+    name = "stringsext",
+    version,
+    after_help = "Exit status: 0 on success, 1 when an input could not be read or the \
+                  output could not be written, 2 on invalid arguments."
+)]
+struct Args {
+    /// Files to scan. Without any file, or with `-`, stdin is read.
+    #[arg(value_name = "FILE")]
+    inputs: Vec<String>,
+
+    /// Encoding to search for, as
+    /// `ENC[,MIN[,AF[,UBF[,GREP[,SCRIPTS[,SCORE[,LANG]]]]]]]`, e.g.
+    /// `UTF-16LE,8`. Empty fields take the value of the corresponding
+    /// option. LANG is `lang` to detect the language in this encoding only. `ascii`
+    /// searches for ASCII only. Repeat for one scanner per encoding.
+    #[arg(short = 'e', long = "encoding", value_name = "ENC")]
+    encodings: Vec<String>,
+
+    /// Minimum number of characters of a string.
+    #[arg(short = 'n', long, value_name = "MIN")]
+    chars_min: Option<String>,
+
+    /// Require all multi-byte characters of a string to be from the same
+    /// Unicode block.
+    #[arg(short = 'r', long)]
+    same_unicode_block: bool,
+
+    /// ASCII filter, e.g. `All-Ctrl+Wsp`: filter names and hex masks
+    /// (`0x...`), combined with `+`, `-` and parentheses. See
+    /// `--list-filters`.
+    #[arg(short = 'a', long, value_name = "AF")]
+    ascii_filter: Option<String>,
+
+    /// Unicode block filter, e.g. `Latin+Greek` or `All-(Private+Cjk)`:
+    /// filter names and hex masks (`0x...`), combined with `+`, `-` and
+    /// parentheses. See `--list-filters`.
+    #[arg(short = 'u', long, value_name = "UBF")]
+    unicode_block_filter: Option<String>,
+
+    /// Accept multi-byte characters of these Unicode scripts only, instead
+    /// of using the Unicode block filter, e.g. `Han+Hiragana+Common`.
+    #[arg(short = 'S', long, value_name = "SCRIPTS")]
+    scripts: Option<String>,
+
+    /// Treat unassigned code points and non-characters as non-printable.
+    /// Reduces false positives when scanning binary data as UTF-16.
+    #[arg(short = 'U', long)]
+    reject_unassigned: bool,
+
+    /// Only print strings matching this regular expression. Strings split
+    /// over several output lines are matched as a whole. Applies to all
+    /// encodings: a regular expression per encoding needs the library's
+    /// `MissionConfig::regex()`.
+    #[arg(short = 'x', long, value_name = "REGEX")]
+    regex: Option<String>,
+
+    /// Do not print strings matching this regular expression. Applies to
+    /// all encodings, like `--regex`.
+    #[arg(short = 'X', long, value_name = "REGEX")]
+    exclude_regex: Option<String>,
+
+    /// Only print strings containing one of the keywords in FILE, one
+    /// keyword per line. The first keyword found is printed with its
+    /// character offset in the string. Applies to all encodings: a keyword
+    /// list per encoding needs the library's `MissionConfig::keywords()`.
+    #[arg(short = 'k', long, value_name = "FILE")]
+    keywords: Option<String>,
+
+    /// Match keywords case-insensitively, with Unicode case folding.
+    #[arg(short = 'i', long)]
+    ignore_case: bool,
+
+    /// Only print strings with at least this plausibility score, from 0
+    /// (noise) to 1 (text). Drops garbage decoded from binary data, e.g.
+    /// with UTF-16. Around 0.5 is a good start.
+    #[arg(short = 'm', long, value_name = "SCORE")]
+    min_score: Option<String>,
+
+    /// Print the detected language of every string and a confidence from
+    /// 0 to 1, e.g. `ru 0.87`. Built-in profiles tell languages sharing a
+    /// script apart. Applies to all encodings, see LANG in `--encoding`.
+    #[arg(short = 'L', long)]
+    language: bool,
+
+    /// Only print strings containing this ASCII character, given as code.
+    #[arg(short = 'g', long, value_name = "GREP")]
+    grep_char: Option<String>,
+
+    /// Maximum number of characters per output line. Longer strings are
+    /// continued on the next line.
+    #[arg(short = 'q', long, value_name = "LEN")]
+    output_line_len: Option<String>,
+
+    /// Number added to every offset.
+    #[arg(short = 's', long, value_name = "OFFSET")]
+    counter_offset: Option<String>,
+
+    /// Print offsets in octal (o), hex (x) or decimal (d). CSV and TSV
+    /// output always has offsets, in hex by default.
+    #[arg(short = 't', long)]
+    radix: Option<Radix>,
+
+    /// Output format.
+    #[arg(short = 'f', long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+
+    /// Write to this file instead of stdout.
+    #[arg(short = 'p', long, value_name = "FILE")]
+    output: Option<PathBuf>,
+
+    /// Number of worker threads. Defaults to the available parallelism.
+    #[arg(short = 'j', long)]
+    workers: Option<usize>,
+
+    /// Instead of scanning for strings, print the offset of every occurrence
+    /// of NEEDLE in the raw input, encoded in each of the encodings and
+    /// always in UTF-16LE, UTF-16BE and x-user-defined, with the names of
+    /// the encodings it was found in. Offsets are hex unless
+    /// `--radix` says otherwise; filters and `--format` are ignored.
+    #[arg(short = 'N', long, value_name = "NEEDLE")]
+    needle: Option<String>,
+
+    /// List all encodings with the labels that select them, then exit.
+    #[arg(short = 'l', long)]
+    list_encodings: bool,
+
+    /// List all ASCII and Unicode block filter names, then exit.
+    #[arg(long)]
+    list_filters: bool,
+
+    /// Memory-map input files instead of reading them, see the library's
+    /// `ScannerBuilder::mmap()` for files changing during the scan.
+    #[cfg(feature = "mmap")]
+    #[arg(long)]
+    mmap: bool,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Format {
+    Text,
+    Csv,
+    Tsv,
+    /// JSON Lines.
+    Json,
+}
+
+/// One of the finding writers.
+enum Output<W: Write> {
+    Text(TextWriter<W>),
+    Delimited(DelimitedWriter<W>),
+    Json(stringexts::JsonLinesWriter<W>),
+}
+
+impl<W: Write> Output<W> {
+    fn write(&mut self, finding: &OwnedFinding) -> stringexts::Result<()> {
+        match self {
+            Output::Text(out) => out.write(finding),
+            Output::Delimited(out) => out.write(finding),
+            Output::Json(out) => out.write(finding),
+        }
+    }
+
+    fn into_inner(self) -> W {
+        match self {
+            Output::Text(out) => out.into_inner(),
+            Output::Delimited(out) => out.into_inner(),
+            Output::Json(out) => out.into_inner(),
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    if args.list_encodings || args.list_filters {
+        let result = list(&args);
+        return match result {
+            Err(e) if e.kind() != io::ErrorKind::BrokenPipe => {
+                eprintln!("stringsext: {}", e);
+                ExitCode::from(EXIT_FAILURE)
+            }
+            _ => ExitCode::SUCCESS,
+        };
+    }
+    let scanner = match build_scanner(&args) {
+        Ok(scanner) => scanner,
+        Err(e) => {
+            eprintln!("stringsext: {}", e);
+            return ExitCode::from(EXIT_USAGE);
+        }
+    };
+    match scan(&args, &scanner) {
+        Ok(summary) if summary.failed_inputs.is_empty() => ExitCode::SUCCESS,
+        Ok(summary) => {
+            for failed in summary.failed_inputs {
+                eprintln!("stringsext: {}", Error::from(failed));
+            }
+            ExitCode::from(EXIT_FAILURE)
+        }
+        // The reader of our output is gone, e.g. `stringsext FILE | head`.
+        Err(Error::Io(e)) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e @ Error::Needle(_)) => {
+            eprintln!("stringsext: {}", e);
+            ExitCode::from(EXIT_USAGE)
+        }
+        Err(e) => {
+            eprintln!("stringsext: {}", e);
+            ExitCode::from(EXIT_FAILURE)
+        }
+    }
+}
+
+fn list(args: &Args) -> io::Result<()> {
+    let mut out = io::stdout().lock();
+    if args.list_encodings {
+        for encoding in stringexts::encodings() {
+            writeln!(out, "{:<16}{}", encoding.name, encoding.labels.join(", "))?;
+        }
+    }
+    if args.list_filters {
+        writeln!(out, "ASCII filters (--ascii-filter):")?;
+        for alias in stringexts::ascii_filter_aliases() {
+            writeln!(
+                out,
+                "  {:<14}{:#034x}  {}",
+                alias.name, alias.mask, alias.description
+            )?;
+        }
+        writeln!(out, "Unicode block filters (--unicode-block-filter):")?;
+        for alias in stringexts::unicode_block_filter_aliases() {
+            writeln!(
+                out,
+                "  {:<14}{:#018x}  {}",
+                alias.name, alias.mask, alias.description
+            )?;
+        }
+    }
+    Ok(())
+}
+
+fn build_scanner(args: &Args) -> stringexts::Result<StringsScanner> {
+    let mut builder = ScannerBuilder::new().on_input_error(OnInputError::Continue);
+    if let Some(workers) = args.workers {
+        builder = builder.workers(workers);
+    }
+    #[cfg(feature = "mmap")]
+    {
+        builder = builder.mmap(args.mmap);
+    }
+    builder = builder.cli_options(CliOptions {
+        scripts: args.scripts.clone(),
+        reject_unassigned: args.reject_unassigned,
+        regex: args.regex.clone(),
+        exclude_regex: args.exclude_regex.clone(),
+        keywords: args.keywords.clone(),
+        ignore_case: args.ignore_case,
+        min_score: args.min_score.clone(),
+        detect_language: args.language,
+    });
+    builder.build_from_args(
+        args.counter_offset.as_ref(),
+        &args.encodings,
+        args.chars_min.as_ref(),
+        args.same_unicode_block,
+        args.ascii_filter.as_ref(),
+        args.unicode_block_filter.as_ref(),
+        args.grep_char.as_ref(),
+        args.output_line_len.as_ref(),
+    )
+}
+
+fn scan(args: &Args, scanner: &StringsScanner) -> stringexts::Result<ScanSummary> {
+    let out: Box<dyn Write> = match &args.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+    let radix = args.radix.unwrap_or(Radix::X);
+    let inputs = if args.inputs.is_empty() {
+        vec![Input::Stdin]
+    } else {
+        args.inputs.iter().map(|arg| Input::from_arg(arg)).collect()
+    };
+
+    if let Some(needle) = &args.needle {
+        let file_column = args.inputs.len() > 1;
+        let summary = scanner.find_needle(needle, inputs, |hit| {
+            if file_column {
+                write!(out, "{}\t", hit.input_label)?;
+            }
+            writeln!(
+                out,
+                "{:>7}\t{}",
+                radix.format(hit.position),
+                hit.encodings.join(",")
+            )?;
+            Ok(())
+        })?;
+        out.flush()?;
+        return Ok(summary);
+    }
+
+    let mut output = match args.format {
+        Format::Text => Output::Text(
+            scanner
+                .text_writer(out, args.radix)
+                .file_column(args.inputs.len() > 1),
+        ),
+        Format::Csv => Output::Delimited(
+            DelimitedWriter::csv(out, radix).language_column(scanner.detects_language()),
+        ),
+        Format::Tsv => Output::Delimited(
+            DelimitedWriter::tsv(out, radix).language_column(scanner.detects_language()),
+        ),
+        Format::Json => Output::Json(stringexts::JsonLinesWriter::new(out)),
+    };
+    let summary = scanner.run_with(inputs, |finding| output.write(&finding))?;
+    output.into_inner().flush()?;
+    Ok(summary)
+}
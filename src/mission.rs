@@ -1,12 +1,16 @@
 #![allow(clippy::too_many_arguments, clippy::type_complexity, dead_code)]
 use crate::{
+    filter::{parse_ascii_filter, parse_unicode_block_filter},
     input::ByteCounter,
+    keywords::Keywords,
     options::{
         ASCII_ENC_LABEL, CHARS_MIN_DEFAULT, COUNTER_OFFSET_DEFAULT, ENCODING_DEFAULT,
         OUTPUT_LINE_CHAR_NB_MAX_DEFAULT, OUTPUT_LINE_CHAR_NB_MIN,
     },
+    script::ScriptFilter,
 };
 use encoding_rs::*;
+use regex::Regex;
 use std::{
     cmp::{self, Eq, Ord},
     fmt,
@@ -176,9 +180,29 @@ impl Ord for Utf8Filter {
     }
 }
 
+/// Number of a mission in the order the missions were given, counting
+/// from 0.
+pub type MissionId = usize;
+
+/// The letter(s) a mission is referred to by in messages: `a` to `z`, then
+/// `aa` to `zz`, `aaa` and so on.
+pub fn scanner_label(mission_id: MissionId) -> String {
+    let mut label = Vec::new();
+    let mut n = mission_id;
+    loop {
+        label.push(b'a' + (n % 26) as u8);
+        if n < 26 {
+            break;
+        }
+        n = n / 26 - 1;
+    }
+    label.reverse();
+    String::from_utf8(label).unwrap()
+}
+
 #[derive(Debug, Clone)]
 pub struct Mission {
-    pub mission_id: u8,
+    pub mission_id: MissionId,
     pub counter_offset: ByteCounter,
     pub encoding: &'static Encoding,
     pub chars_min_nb: u8,
@@ -186,6 +210,216 @@ pub struct Mission {
     pub filter: Utf8Filter,
     pub output_line_char_nb_max: usize,
     pub print_encoding_as_ascii: bool,
+    /// When set, replaces the Unicode block filter.
+    pub script_filter: Option<Arc<ScriptFilter>>,
+    /// Unassigned code points and non-characters end a string.
+    pub reject_unassigned: bool,
+    /// Only strings matching this are kept.
+    pub regex: Option<Regex>,
+    /// Strings matching this are dropped.
+    pub exclude_regex: Option<Regex>,
+    /// Only strings containing one of these are kept.
+    pub keywords: Option<Arc<Keywords>>,
+    /// Only strings with at least this plausibility score are kept.
+    pub min_score: Option<f32>,
+    /// Strings are tagged with their plausibility score, also without
+    /// `min_score`.
+    pub score: bool,
+    /// Strings are tagged with their language.
+    pub detect_language: bool,
+}
+
+impl Mission {
+    /// See `scanner_label()`.
+    pub fn scanner_label(&self) -> String {
+        scanner_label(self.mission_id)
+    }
+
+    /// Name of the encoding as given by the user, i.e. `ascii` for the
+    /// `ascii` pseudo-encoding.
+    pub fn encoding_name(&self) -> &'static str {
+        if self.print_encoding_as_ascii {
+            ASCII_ENC_LABEL
+        } else {
+            self.encoding.name()
+        }
+    }
+}
+
+/// Typed configuration of one mission. This is what the string based
+/// `Missions::new()` parses its arguments into. Library users can build it
+/// directly, e.g. `MissionConfig::new(UTF_16LE).chars_min(8)`, and pass it to
+/// `ScannerBuilder::mission()`.
+#[derive(Debug, Clone)]
+pub struct MissionConfig {
+    pub encoding: &'static Encoding,
+    /// Set for the `ascii` pseudo-encoding, which is scanned with the
+    /// `x-user-defined` decoder.
+    pub print_encoding_as_ascii: bool,
+    pub chars_min_nb: u8,
+    pub require_same_unicode_block: bool,
+    pub filter: Utf8Filter,
+    /// When set, replaces the Unicode block filter `filter.ubf`.
+    pub script_filter: Option<Arc<ScriptFilter>>,
+    /// Treat unassigned code points and non-characters, e.g. U+FFFF, as
+    /// non-printable. Decoding random bytes as UTF-16 or GB18030 produces
+    /// many of them.
+    pub reject_unassigned: bool,
+    /// Keeps only strings matching this regular expression. It is matched
+    /// against the whole string, also when the string is reported in
+    /// several findings.
+    pub regex: Option<Regex>,
+    /// Drops strings matching this regular expression, see `regex`.
+    pub exclude_regex: Option<Regex>,
+    /// Keeps only strings containing one of the keywords, see `regex`. The
+    /// first keyword found is reported in `OwnedFinding::keyword_match`.
+    pub keywords: Option<Arc<Keywords>>,
+    /// Keeps only strings with a `plausibility()` score of at least
+    /// `min_score`, see `regex`. The score is reported in
+    /// `OwnedFinding::score`.
+    pub min_score: Option<f32>,
+    /// Reports the `plausibility()` score in `OwnedFinding::score` without
+    /// dropping any string. A `min_score` implies it.
+    pub score: bool,
+    /// Tags strings with their `detect_language()` guess, reported in
+    /// `OwnedFinding::language`. Like `regex`, the whole string is looked
+    /// at.
+    pub detect_language: bool,
+    pub output_line_char_nb_max: usize,
+    pub counter_offset: ByteCounter,
+}
+
+impl MissionConfig {
+    /// A mission for `encoding` with the same defaults `Missions::new()`
+    /// applies to unspecified options.
+    pub fn new(encoding: &'static Encoding) -> Self {
+        Self {
+            encoding,
+            print_encoding_as_ascii: false,
+            chars_min_nb: CHARS_MIN_DEFAULT,
+            require_same_unicode_block: false,
+            filter: UTF8_FILTER_NON_ASCII_MODE_DEFAULT,
+            script_filter: None,
+            reject_unassigned: false,
+            regex: None,
+            exclude_regex: None,
+            keywords: None,
+            min_score: None,
+            score: false,
+            detect_language: false,
+            output_line_char_nb_max: OUTPUT_LINE_CHAR_NB_MAX_DEFAULT,
+            counter_offset: COUNTER_OFFSET_DEFAULT,
+        }
+    }
+
+    /// A mission for the `ascii` pseudo-encoding, the GNU strings
+    /// replacement mode.
+    pub fn ascii() -> Self {
+        Self {
+            print_encoding_as_ascii: true,
+            filter: UTF8_FILTER_ASCII_MODE_DEFAULT,
+            ..Self::new(X_USER_DEFINED)
+        }
+    }
+
+    pub fn chars_min(mut self, chars_min_nb: u8) -> Self {
+        self.chars_min_nb = chars_min_nb;
+        self
+    }
+
+    pub fn same_unicode_block(mut self, require_same_unicode_block: bool) -> Self {
+        self.require_same_unicode_block = require_same_unicode_block;
+        self
+    }
+
+    pub fn filter(mut self, filter: Utf8Filter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn ascii_filter(mut self, af: u128) -> Self {
+        self.filter.af = af;
+        self
+    }
+
+    pub fn unicode_block_filter(mut self, ubf: u64) -> Self {
+        self.filter.ubf = ubf;
+        self
+    }
+
+    /// Accepts multi-byte characters by their Unicode script instead of by
+    /// the Unicode block filter, e.g. `ScriptFilter::parse("Han+Common")`.
+    pub fn script_filter(mut self, script_filter: Option<ScriptFilter>) -> Self {
+        self.script_filter = script_filter.map(Arc::new);
+        self
+    }
+
+    pub fn reject_unassigned(mut self, reject_unassigned: bool) -> Self {
+        self.reject_unassigned = reject_unassigned;
+        self
+    }
+
+    /// Keeps only strings matching `regex`. Unlike `--regex`, which applies
+    /// to all missions, every mission can have its own.
+    ///
+    /// The parts of a string cut at chunk boundaries or at
+    /// `output_line_len()` are matched as one string. Strings longer than
+    /// 1 MiB are matched in pieces of about 1 MiB, to bound the memory
+    /// holding them. The same holds for `exclude_regex()`, `keywords()`, the
+    /// score and the language.
+    pub fn regex(mut self, regex: Option<Regex>) -> Self {
+        self.regex = regex;
+        self
+    }
+
+    /// Drops strings matching `exclude_regex`, per mission like `regex()`.
+    pub fn exclude_regex(mut self, exclude_regex: Option<Regex>) -> Self {
+        self.exclude_regex = exclude_regex;
+        self
+    }
+
+    /// Keeps only strings containing one of `keywords`, per mission like
+    /// `regex()`.
+    pub fn keywords(mut self, keywords: Option<Keywords>) -> Self {
+        self.keywords = keywords.map(Arc::new);
+        self
+    }
+
+    pub fn min_score(mut self, min_score: Option<f32>) -> Self {
+        self.min_score = min_score;
+        self
+    }
+
+    pub fn score(mut self, score: bool) -> Self {
+        self.score = score;
+        self
+    }
+
+    pub fn detect_language(mut self, detect_language: bool) -> Self {
+        self.detect_language = detect_language;
+        self
+    }
+
+    pub fn grep_char(mut self, grep_char: Option<u8>) -> Self {
+        self.filter.grep_char = grep_char;
+        self
+    }
+
+    pub fn output_line_len(mut self, output_line_char_nb_max: usize) -> Self {
+        self.output_line_char_nb_max = output_line_char_nb_max;
+        self
+    }
+
+    pub fn counter_offset(mut self, counter_offset: ByteCounter) -> Self {
+        self.counter_offset = counter_offset;
+        self
+    }
+}
+
+impl Default for MissionConfig {
+    fn default() -> Self {
+        Self::new(UTF_8)
+    }
 }
 
 #[derive(Debug)]
@@ -214,34 +448,103 @@ macro_rules! parse_integer {
     }};
 }
 
-#[macro_export]
 macro_rules! parse_filter_parameter {
-    ($s:expr, $x_from_str_radix:expr, $list:expr) => {{
+    ($s:expr, $parse:expr) => {{
         match $s {
-            Some(s) if s.trim().len() >= 2 && s.trim()[..2] == *"0x" => {
-                Some($x_from_str_radix(&s.trim()[2..], 16)?)
-            }
-            Some(s) if s.is_empty() => None,
-            Some(s) => {
-                let s = s.trim();
-                let mut oubf = None;
-                for (ubf_name, ubf, _) in $list.iter() {
-                    if s.len() <= ubf_name.len() && *s.as_bytes() == ubf_name[..s.len()] {
-                        oubf = Some(*ubf);
-                        break;
-                    };
-                }
-                if oubf.is_some() {
-                    oubf
-                } else {
-                    return Err($crate::error::Error::InvalidFilterName(s.to_string()));
-                }
-            }
+            Some(s) if s.trim().is_empty() => None,
+            Some(s) => Some($parse(s)?),
             None => None,
         }
     }};
 }
 
+/// The fields of one `--encoding` option, `None` when empty or missing.
+struct EncOpt<'a> {
+    enc_name: Option<&'a str>,
+    chars_min_nb: Option<u8>,
+    filter_af: Option<u128>,
+    filter_ubf: Option<u64>,
+    filter_grep_char: Option<u8>,
+    scripts: Option<ScriptFilter>,
+    min_score: Option<f32>,
+    detect_language: bool,
+}
+
+fn parse_min_score(s: &str) -> crate::Result<f32> {
+    match s.trim().parse::<f32>() {
+        Ok(min_score) if (0.0..=1.0).contains(&min_score) => Ok(min_score),
+        _ => Err(crate::error::Error::MinScore(s.to_string())),
+    }
+}
+
+/// The LANG field of `--encoding`: `lang` detects the language.
+fn parse_language_field(s: &str) -> crate::Result<bool> {
+    match s.trim() {
+        "lang" => Ok(true),
+        _ => Err(crate::error::Error::LanguageField(s.to_string())),
+    }
+}
+
+/// The command line style options that apply to all missions parsed from
+/// `--encoding` options, see `ScannerBuilder::cli_options()`. Like the
+/// fields of `--encoding`, empty strings count as missing.
+#[derive(Debug, Clone, Default)]
+pub struct CliOptions {
+    /// Scripts accepted in multi-byte characters, e.g. `Han+Common`, for
+    /// missions without a SCRIPTS field.
+    pub scripts: Option<String>,
+    pub reject_unassigned: bool,
+    /// Keep only strings matching this regular expression.
+    pub regex: Option<String>,
+    /// Drop strings matching this regular expression.
+    pub exclude_regex: Option<String>,
+    /// Path of a keyword list, one keyword per line.
+    pub keywords: Option<String>,
+    /// Compare keywords with Unicode case folding.
+    pub ignore_case: bool,
+    /// Minimum plausibility score for missions without a SCORE field.
+    pub min_score: Option<String>,
+    /// Detect the language in all missions, not only in those with a LANG
+    /// field.
+    pub detect_language: bool,
+}
+
+impl CliOptions {
+    /// Parses the options and sets them in every mission of `configs`. The
+    /// scripts and minimum score of a mission's `--encoding` option take
+    /// precedence.
+    pub(crate) fn apply(&self, configs: Vec<MissionConfig>) -> crate::Result<Vec<MissionConfig>> {
+        let flag_scripts =
+            parse_filter_parameter!(self.scripts.as_ref(), ScriptFilter::parse).map(Arc::new);
+
+        let flag_regex = parse_filter_parameter!(self.regex.as_ref(), |s: &str| Regex::new(s));
+
+        let flag_exclude_regex =
+            parse_filter_parameter!(self.exclude_regex.as_ref(), |s: &str| Regex::new(s));
+
+        let flag_keywords = parse_filter_parameter!(self.keywords.as_ref(), |path: &str| {
+            Keywords::from_file(path, self.ignore_case)
+        })
+        .map(Arc::new);
+
+        let flag_min_score = parse_filter_parameter!(self.min_score.as_ref(), parse_min_score);
+
+        Ok(configs
+            .into_iter()
+            .map(|config| MissionConfig {
+                script_filter: config.script_filter.or_else(|| flag_scripts.clone()),
+                reject_unassigned: self.reject_unassigned,
+                regex: flag_regex.clone(),
+                exclude_regex: flag_exclude_regex.clone(),
+                keywords: flag_keywords.clone(),
+                min_score: config.min_score.or(flag_min_score),
+                detect_language: config.detect_language || self.detect_language,
+                ..config
+            })
+            .collect())
+    }
+}
+
 impl Missions {
     pub fn new(
         flag_counter_offset: Option<&String>,
@@ -253,6 +556,29 @@ impl Missions {
         flag_grep_char: Option<&String>,
         flag_output_line_len: Option<&String>,
     ) -> crate::Result<Self> {
+        Self::from_configs(Self::parse_configs(
+            flag_counter_offset,
+            flag_encoding,
+            flag_chars_min_nb,
+            flag_same_unicode_block,
+            flag_ascii_filter,
+            flag_unicode_block_filter,
+            flag_grep_char,
+            flag_output_line_len,
+        )?)
+    }
+
+    /// Parses the arguments of `new()` into typed mission configurations.
+    pub(crate) fn parse_configs(
+        flag_counter_offset: Option<&String>,
+        flag_encoding: &[String],
+        flag_chars_min_nb: Option<&String>,
+        flag_same_unicode_block: bool,
+        flag_ascii_filter: Option<&String>,
+        flag_unicode_block_filter: Option<&String>,
+        flag_grep_char: Option<&String>,
+        flag_output_line_len: Option<&String>,
+    ) -> crate::Result<Vec<MissionConfig>> {
         let flag_counter_offset = parse_integer!(
             flag_counter_offset,
             ByteCounter::from_str_radix,
@@ -261,17 +587,10 @@ impl Missions {
 
         let flag_chars_min_nb = parse_integer!(flag_chars_min_nb, u8::from_str_radix, u8::from_str);
 
-        let flag_ascii_filter = parse_filter_parameter!(
-            flag_ascii_filter,
-            u128::from_str_radix,
-            ASCII_FILTER_ALIASSE
-        );
+        let flag_ascii_filter = parse_filter_parameter!(flag_ascii_filter, parse_ascii_filter);
 
-        let flag_unicode_block_filter = parse_filter_parameter!(
-            flag_unicode_block_filter,
-            u64::from_str_radix,
-            UNICODE_BLOCK_FILTER_ALIASSE
-        );
+        let flag_unicode_block_filter =
+            parse_filter_parameter!(flag_unicode_block_filter, parse_unicode_block_filter);
 
         let flag_grep_char = parse_integer!(flag_grep_char, u8::from_str_radix, u8::from_str);
         if let Some(m) = flag_grep_char {
@@ -300,9 +619,17 @@ impl Missions {
             flag_encoding.iter()
         };
 
-        for (mission_id, enc_opt) in enc_iter.enumerate() {
-            let (enc_name, chars_min_nb, filter_af, filter_ubf, filter_grep_char) =
-                Self::parse_enc_opt(enc_opt)?;
+        for enc_opt in enc_iter {
+            let EncOpt {
+                enc_name,
+                chars_min_nb,
+                filter_af,
+                filter_ubf,
+                filter_grep_char,
+                scripts,
+                min_score,
+                detect_language,
+            } = Self::parse_enc_opt(enc_opt)?;
 
             let mut enc_name = match enc_name {
                 Some(s) => s,
@@ -329,14 +656,6 @@ impl Missions {
                 None => OUTPUT_LINE_CHAR_NB_MAX_DEFAULT,
             };
 
-            if output_line_char_nb_max < OUTPUT_LINE_CHAR_NB_MIN {
-                return Err(crate::error::Error::ScannerMinimumOutputLineLength(
-                    char::from((mission_id + 97) as u8).to_string(),
-                    OUTPUT_LINE_CHAR_NB_MIN,
-                    output_line_char_nb_max,
-                ));
-            }
-
             let filter_af = filter_af.unwrap_or_else(|| {
                 flag_ascii_filter.unwrap_or(if enc_name == ASCII_ENC_LABEL {
                     UTF8_FILTER_ASCII_MODE_DEFAULT.af
@@ -367,15 +686,6 @@ impl Missions {
                 },
             };
 
-            if let Some(m) = filter_grep_char {
-                if m > 127 {
-                    return Err(crate::error::Error::ScanerGrepCode(
-                        char::from((mission_id + 97) as u8).to_string(),
-                        m,
-                    ));
-                }
-            }
-
             let filter = Utf8Filter {
                 af: filter_af,
                 ubf: filter_ubf,
@@ -388,18 +698,78 @@ impl Missions {
                 enc_name = "x-user-defined"
             };
 
-            let encoding = &Encoding::for_label(enc_name.as_bytes())
+            // Labels of the `replacement` encoding, e.g. `iso-2022-kr`, are
+            // refused: it decodes any input to a single U+FFFD.
+            let encoding = Encoding::for_label_no_replacement(enc_name.as_bytes())
                 .ok_or(crate::error::Error::Encoding(enc_name.to_string()))?;
 
-            v.push(Arc::new(Mission {
-                counter_offset,
+            v.push(MissionConfig {
                 encoding,
+                print_encoding_as_ascii,
                 chars_min_nb,
                 require_same_unicode_block,
                 filter,
+                script_filter: scripts.map(Arc::new),
+                reject_unassigned: false,
+                regex: None,
+                exclude_regex: None,
+                keywords: None,
+                min_score,
+                score: false,
+                detect_language,
                 output_line_char_nb_max,
-                mission_id: mission_id as u8,
-                print_encoding_as_ascii,
+                counter_offset,
+            });
+        }
+
+        Ok(v)
+    }
+
+    /// Validates the typed mission configurations and numbers them in the
+    /// given order. An empty `configs` results in one default mission.
+    pub fn from_configs(configs: Vec<MissionConfig>) -> crate::Result<Self> {
+        let configs = if configs.is_empty() {
+            vec![MissionConfig::default()]
+        } else {
+            configs
+        };
+
+        let mut v = Vec::with_capacity(configs.len());
+        for (mission_id, config) in configs.into_iter().enumerate() {
+            if config.output_line_char_nb_max < OUTPUT_LINE_CHAR_NB_MIN {
+                return Err(crate::error::Error::ScannerMinimumOutputLineLength(
+                    scanner_label(mission_id),
+                    OUTPUT_LINE_CHAR_NB_MIN,
+                    config.output_line_char_nb_max,
+                ));
+            }
+
+            if let Some(m) = config.filter.grep_char {
+                if m > 127 {
+                    return Err(crate::error::Error::ScanerGrepCode(
+                        scanner_label(mission_id),
+                        m,
+                    ));
+                }
+            }
+
+            v.push(Arc::new(Mission {
+                counter_offset: config.counter_offset,
+                encoding: config.encoding,
+                chars_min_nb: config.chars_min_nb,
+                require_same_unicode_block: config.require_same_unicode_block,
+                filter: config.filter,
+                script_filter: config.script_filter,
+                reject_unassigned: config.reject_unassigned,
+                regex: config.regex,
+                exclude_regex: config.exclude_regex,
+                keywords: config.keywords,
+                min_score: config.min_score,
+                score: config.score,
+                detect_language: config.detect_language,
+                output_line_char_nb_max: config.output_line_char_nb_max,
+                mission_id,
+                print_encoding_as_ascii: config.print_encoding_as_ascii,
             }));
         }
 
@@ -412,15 +782,7 @@ impl Missions {
     }
 
     #[inline]
-    fn parse_enc_opt(
-        enc_opt: &str,
-    ) -> crate::Result<(
-        Option<&str>,
-        Option<u8>,
-        Option<u128>,
-        Option<u64>,
-        Option<u8>,
-    )> {
+    fn parse_enc_opt(enc_opt: &str) -> crate::Result<EncOpt<'_>> {
         let mut i = enc_opt.split_terminator(',');
 
         let enc_name = match i.next() {
@@ -431,17 +793,92 @@ impl Missions {
 
         let chars_min_nb = parse_integer!(i.next(), u8::from_str_radix, u8::from_str);
 
-        let filter_af =
-            parse_filter_parameter!(i.next(), u128::from_str_radix, ASCII_FILTER_ALIASSE);
+        let filter_af = parse_filter_parameter!(i.next(), parse_ascii_filter);
 
-        let filter_ubf =
-            parse_filter_parameter!(i.next(), u64::from_str_radix, UNICODE_BLOCK_FILTER_ALIASSE);
+        let filter_ubf = parse_filter_parameter!(i.next(), parse_unicode_block_filter);
 
         let grep_char = parse_integer!(i.next(), u8::from_str_radix, u8::from_str);
 
+        let scripts = parse_filter_parameter!(i.next(), ScriptFilter::parse);
+
+        let min_score = parse_filter_parameter!(i.next(), parse_min_score);
+
+        let detect_language =
+            parse_filter_parameter!(i.next(), parse_language_field).unwrap_or(false);
+
         if i.next().is_some() {
             return Err(crate::error::Error::TooManyEncodings(enc_opt.to_string()));
         }
-        Ok((enc_name, chars_min_nb, filter_af, filter_ubf, grep_char))
+        Ok(EncOpt {
+            enc_name,
+            chars_min_nb,
+            filter_af,
+            filter_ubf,
+            filter_grep_char: grep_char,
+            scripts,
+            min_score,
+            detect_language,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scanner_labels_count_like_spreadsheet_columns() {
+        for (mission_id, label) in [
+            (0, "a"),
+            (1, "b"),
+            (25, "z"),
+            (26, "aa"),
+            (27, "ab"),
+            (51, "az"),
+            (52, "ba"),
+            (701, "zz"),
+            (702, "aaa"),
+        ] {
+            assert_eq!(scanner_label(mission_id), label);
+        }
+    }
+
+    #[test]
+    fn replacement_labels_are_unknown_encodings() {
+        for label in [
+            "replacement",
+            "csiso2022kr",
+            "hz-gb-2312",
+            "iso-2022-cn",
+            "iso-2022-cn-ext",
+            "iso-2022-kr",
+        ] {
+            let result = Missions::parse_configs(
+                None,
+                &[label.to_string()],
+                None,
+                false,
+                None,
+                None,
+                None,
+                None,
+            );
+            assert!(
+                matches!(&result, Err(crate::error::Error::Encoding(name)) if name == label),
+                "label `{}`",
+                label
+            );
+        }
+        assert!(Missions::parse_configs(
+            None,
+            &["iso-2022-jp".to_string()],
+            None,
+            false,
+            None,
+            None,
+            None,
+            None
+        )
+        .is_ok());
     }
 }
@@ -0,0 +1,370 @@
+//! The names the scanner accepts: encoding labels and filter aliases.
+
+use crate::{
+    mission::{ASCII_FILTER_ALIASSE, UNICODE_BLOCK_FILTER_ALIASSE},
+    options::ASCII_ENC_LABEL,
+};
+use encoding_rs::{Encoding, X_USER_DEFINED};
+use std::str;
+
+/// An encoding and all labels that select it, e.g. in `--encoding`.
+/// Labels are matched case-insensitively.
+#[derive(Debug, Clone, Copy)]
+pub struct EncodingLabels {
+    /// The name reported in findings.
+    pub name: &'static str,
+    pub encoding: &'static Encoding,
+    pub labels: &'static [&'static str],
+}
+
+/// A named filter mask, accepted by `--ascii-filter` or
+/// `--unicode-block-filter`. A prefix of the name selects the first alias
+/// it matches, e.g. `Cy` selects `Cyrillic`.
+#[derive(Debug, Clone, Copy)]
+pub struct FilterAlias<M> {
+    pub name: &'static str,
+    pub mask: M,
+    pub description: &'static str,
+}
+
+/// The WHATWG labels, grouped by the encoding they select, see
+/// <https://encoding.spec.whatwg.org/#names-and-labels>. `ascii` is missing
+/// for windows-1252, because it selects the `ascii` pseudo-encoding here.
+/// The `replacement` encoding is missing too: it decodes any input to a
+/// single U+FFFD and finds nothing.
+const ENCODING_LABELS: [(&str, &[&str]); 39] = [
+    (
+        "Big5",
+        &["big5", "big5-hkscs", "cn-big5", "csbig5", "x-x-big5"],
+    ),
+    ("EUC-JP", &["cseucpkdfmtjapanese", "euc-jp", "x-euc-jp"]),
+    (
+        "EUC-KR",
+        &[
+            "cseuckr",
+            "csksc56011987",
+            "euc-kr",
+            "iso-ir-149",
+            "korean",
+            "ks_c_5601-1987",
+            "ks_c_5601-1989",
+            "ksc5601",
+            "ksc_5601",
+            "windows-949",
+        ],
+    ),
+    ("gb18030", &["gb18030"]),
+    (
+        "GBK",
+        &[
+            "chinese",
+            "csgb2312",
+            "csiso58gb231280",
+            "gb2312",
+            "gb_2312",
+            "gb_2312-80",
+            "gbk",
+            "iso-ir-58",
+            "x-gbk",
+        ],
+    ),
+    ("IBM866", &["866", "cp866", "csibm866", "ibm866"]),
+    ("ISO-2022-JP", &["csiso2022jp", "iso-2022-jp"]),
+    (
+        "ISO-8859-10",
+        &[
+            "csisolatin6",
+            "iso-8859-10",
+            "iso-ir-157",
+            "iso8859-10",
+            "iso885910",
+            "l6",
+            "latin6",
+        ],
+    ),
+    ("ISO-8859-13", &["iso-8859-13", "iso8859-13", "iso885913"]),
+    ("ISO-8859-14", &["iso-8859-14", "iso8859-14", "iso885914"]),
+    (
+        "ISO-8859-15",
+        &[
+            "csisolatin9",
+            "iso-8859-15",
+            "iso8859-15",
+            "iso885915",
+            "iso_8859-15",
+            "l9",
+        ],
+    ),
+    ("ISO-8859-16", &["iso-8859-16"]),
+    (
+        "ISO-8859-2",
+        &[
+            "csisolatin2",
+            "iso-8859-2",
+            "iso-ir-101",
+            "iso8859-2",
+            "iso88592",
+            "iso_8859-2",
+            "iso_8859-2:1987",
+            "l2",
+            "latin2",
+        ],
+    ),
+    (
+        "ISO-8859-3",
+        &[
+            "csisolatin3",
+            "iso-8859-3",
+            "iso-ir-109",
+            "iso8859-3",
+            "iso88593",
+            "iso_8859-3",
+            "iso_8859-3:1988",
+            "l3",
+            "latin3",
+        ],
+    ),
+    (
+        "ISO-8859-4",
+        &[
+            "csisolatin4",
+            "iso-8859-4",
+            "iso-ir-110",
+            "iso8859-4",
+            "iso88594",
+            "iso_8859-4",
+            "iso_8859-4:1988",
+            "l4",
+            "latin4",
+        ],
+    ),
+    (
+        "ISO-8859-5",
+        &[
+            "csisolatincyrillic",
+            "cyrillic",
+            "iso-8859-5",
+            "iso-ir-144",
+            "iso8859-5",
+            "iso88595",
+            "iso_8859-5",
+            "iso_8859-5:1988",
+        ],
+    ),
+    (
+        "ISO-8859-6",
+        &[
+            "arabic",
+            "asmo-708",
+            "csiso88596e",
+            "csiso88596i",
+            "csisolatinarabic",
+            "ecma-114",
+            "iso-8859-6",
+            "iso-8859-6-e",
+            "iso-8859-6-i",
+            "iso-ir-127",
+            "iso8859-6",
+            "iso88596",
+            "iso_8859-6",
+            "iso_8859-6:1987",
+        ],
+    ),
+    (
+        "ISO-8859-7",
+        &[
+            "csisolatingreek",
+            "ecma-118",
+            "elot_928",
+            "greek",
+            "greek8",
+            "iso-8859-7",
+            "iso-ir-126",
+            "iso8859-7",
+            "iso88597",
+            "iso_8859-7",
+            "iso_8859-7:1987",
+            "sun_eu_greek",
+        ],
+    ),
+    (
+        "ISO-8859-8",
+        &[
+            "csiso88598e",
+            "csisolatinhebrew",
+            "hebrew",
+            "iso-8859-8",
+            "iso-8859-8-e",
+            "iso-ir-138",
+            "iso8859-8",
+            "iso88598",
+            "iso_8859-8",
+            "iso_8859-8:1988",
+            "visual",
+        ],
+    ),
+    ("ISO-8859-8-I", &["csiso88598i", "iso-8859-8-i", "logical"]),
+    ("KOI8-R", &["cskoi8r", "koi", "koi8", "koi8-r", "koi8_r"]),
+    ("KOI8-U", &["koi8-ru", "koi8-u"]),
+    (
+        "macintosh",
+        &["csmacintosh", "mac", "macintosh", "x-mac-roman"],
+    ),
+    (
+        "Shift_JIS",
+        &[
+            "csshiftjis",
+            "ms932",
+            "ms_kanji",
+            "shift-jis",
+            "shift_jis",
+            "sjis",
+            "windows-31j",
+            "x-sjis",
+        ],
+    ),
+    ("UTF-16BE", &["unicodefffe", "utf-16be"]),
+    (
+        "UTF-16LE",
+        &[
+            "csunicode",
+            "iso-10646-ucs-2",
+            "ucs-2",
+            "unicode",
+            "unicodefeff",
+            "utf-16",
+            "utf-16le",
+        ],
+    ),
+    (
+        "UTF-8",
+        &[
+            "unicode-1-1-utf-8",
+            "unicode11utf8",
+            "unicode20utf8",
+            "utf-8",
+            "utf8",
+            "x-unicode20utf8",
+        ],
+    ),
+    ("windows-1250", &["cp1250", "windows-1250", "x-cp1250"]),
+    ("windows-1251", &["cp1251", "windows-1251", "x-cp1251"]),
+    (
+        "windows-1252",
+        &[
+            "ansi_x3.4-1968",
+            "cp1252",
+            "cp819",
+            "csisolatin1",
+            "ibm819",
+            "iso-8859-1",
+            "iso-ir-100",
+            "iso8859-1",
+            "iso88591",
+            "iso_8859-1",
+            "iso_8859-1:1987",
+            "l1",
+            "latin1",
+            "us-ascii",
+            "windows-1252",
+            "x-cp1252",
+        ],
+    ),
+    ("windows-1253", &["cp1253", "windows-1253", "x-cp1253"]),
+    (
+        "windows-1254",
+        &[
+            "cp1254",
+            "csisolatin5",
+            "iso-8859-9",
+            "iso-ir-148",
+            "iso8859-9",
+            "iso88599",
+            "iso_8859-9",
+            "iso_8859-9:1989",
+            "l5",
+            "latin5",
+            "windows-1254",
+            "x-cp1254",
+        ],
+    ),
+    ("windows-1255", &["cp1255", "windows-1255", "x-cp1255"]),
+    ("windows-1256", &["cp1256", "windows-1256", "x-cp1256"]),
+    ("windows-1257", &["cp1257", "windows-1257", "x-cp1257"]),
+    ("windows-1258", &["cp1258", "windows-1258", "x-cp1258"]),
+    (
+        "windows-874",
+        &[
+            "dos-874",
+            "iso-8859-11",
+            "iso8859-11",
+            "iso885911",
+            "tis-620",
+            "windows-874",
+        ],
+    ),
+    ("x-mac-cyrillic", &["x-mac-cyrillic", "x-mac-ukrainian"]),
+    ("x-user-defined", &["x-user-defined"]),
+];
+
+/// All encodings and their labels: first the `ascii` pseudo-encoding, then
+/// the WHATWG encodings, ordered by name.
+pub fn encodings() -> impl Iterator<Item = EncodingLabels> {
+    let ascii = EncodingLabels {
+        name: ASCII_ENC_LABEL,
+        encoding: X_USER_DEFINED,
+        labels: &[ASCII_ENC_LABEL],
+    };
+    std::iter::once(ascii).chain(
+        ENCODING_LABELS
+            .iter()
+            .map(|&(name, labels)| EncodingLabels {
+                name,
+                // All labels in the table are known to `encoding_rs`.
+                encoding: Encoding::for_label(labels[0].as_bytes()).unwrap(),
+                labels,
+            }),
+    )
+}
+
+/// All aliases accepted by the ASCII filter.
+pub fn ascii_filter_aliases() -> impl Iterator<Item = FilterAlias<u128>> {
+    let aliases: &'static [_] = &ASCII_FILTER_ALIASSE;
+    aliases
+        .iter()
+        .map(|(name, mask, description)| filter_alias(name, *mask, description))
+}
+
+/// All aliases accepted by the Unicode block filter.
+pub fn unicode_block_filter_aliases() -> impl Iterator<Item = FilterAlias<u64>> {
+    let aliases: &'static [_] = &UNICODE_BLOCK_FILTER_ALIASSE;
+    aliases
+        .iter()
+        .map(|(name, mask, description)| filter_alias(name, *mask, description))
+}
+
+/// Converts an entry of the fixed-width alias tables.
+fn filter_alias<M>(name: &'static [u8], mask: M, description: &'static [u8]) -> FilterAlias<M> {
+    // The tables are ASCII only.
+    FilterAlias {
+        name: str::from_utf8(name).unwrap().trim_end(),
+        mask,
+        description: str::from_utf8(description).unwrap().trim_end(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_label_selects_its_encoding() {
+        for (name, labels) in ENCODING_LABELS {
+            for label in labels {
+                let encoding = Encoding::for_label(label.as_bytes())
+                    .unwrap_or_else(|| panic!("unknown label `{}`", label));
+                assert_eq!(encoding.name(), name, "label `{}`", label);
+            }
+        }
+    }
+}
@@ -0,0 +1,293 @@
+//! Search for a known string in the raw input bytes, in every encoding of
+//! the scanner's missions.
+//!
+//! The needle is encoded in every mission's encoding, and always in
+//! UTF-16LE, UTF-16BE and x-user-defined, which hide strings from most
+//! other tools. It is encoded once per encoding, with the `encoding_rs`
+//! encoders, and by hand for the `ascii` pseudo-encoding and UTF-16, which
+//! `encoding_rs` can decode but not encode. Encodings that produce the same
+//! bytes, e.g. UTF-8 and windows-1252 for an ASCII needle, share one
+//! pattern. All patterns are searched in one pass over the input, at any
+//! byte offset, so UTF-16 is found at both alignments.
+//!
+//! The input is read chunk by chunk with `Slicer::overlapping()`. The last
+//! bytes of every chunk are searched again together with the next chunk, so
+//! that hits spanning a chunk boundary are found.
+
+use crate::{
+    error::Error,
+    finding::ScanSummary,
+    input::{ByteCounter, FailedInput, Input, InputFileId, InputLabel, Slicer},
+    mission::Missions,
+    options::OnInputError,
+};
+use aho_corasick::AhoCorasick;
+use encoding_rs::{EncoderResult, Encoding, UTF_16BE, UTF_16LE, X_USER_DEFINED};
+use std::sync::Arc;
+
+/// One occurrence of the needle.
+#[derive(Debug, Clone)]
+pub struct NeedleHit {
+    pub input_file_id: Option<InputFileId>,
+    /// The path or label of the input with `input_file_id`.
+    pub input_label: Arc<InputLabel>,
+    /// Byte offset of the hit in the input.
+    pub position: ByteCounter,
+    /// Length of the encoded needle in bytes.
+    pub len: usize,
+    /// Names of all encodings the needle is encoded to these bytes in.
+    pub encodings: Arc<[&'static str]>,
+}
+
+/// The needle, encoded in all encodings of the missions.
+pub(crate) struct Needle {
+    automaton: AhoCorasick,
+    /// The encoding names of every pattern of `automaton`.
+    encodings: Vec<Arc<[&'static str]>>,
+    /// Longest pattern length minus one: the number of bytes searched again
+    /// with the next chunk.
+    overlap: usize,
+}
+
+impl Needle {
+    pub fn new(needle: &str, missions: &Missions) -> crate::Result<Self> {
+        if needle.is_empty() {
+            return Err(Error::Needle("the needle is empty".to_string()));
+        }
+        let mut patterns: Vec<(Vec<u8>, Vec<&'static str>)> = Vec::new();
+        let missions = missions.iter().map(|mission| {
+            (
+                mission.encoding_name(),
+                mission.encoding,
+                mission.print_encoding_as_ascii,
+            )
+        });
+        let always = ALWAYS_SEARCHED
+            .iter()
+            .filter(|&&encoding| {
+                // The `ascii` pseudo-encoding does not count as x-user-defined.
+                !missions
+                    .clone()
+                    .any(|(_, e, is_ascii)| e == encoding && !is_ascii)
+            })
+            .map(|&encoding| (encoding.name(), encoding, false));
+        for (name, encoding, is_ascii) in missions.clone().chain(always) {
+            let bytes = if is_ascii {
+                needle.is_ascii().then(|| needle.as_bytes().to_vec())
+            } else {
+                encode(needle, encoding)
+            };
+            let Some(bytes) = bytes else {
+                continue;
+            };
+            match patterns.iter_mut().find(|(pattern, _)| *pattern == bytes) {
+                Some((_, names)) if names.contains(&name) => {}
+                Some((_, names)) => names.push(name),
+                None => patterns.push((bytes, vec![name])),
+            }
+        }
+        if patterns.is_empty() {
+            return Err(Error::Needle(format!(
+                "`{}` can not be encoded in any of the encodings",
+                needle
+            )));
+        }
+        let automaton = AhoCorasick::new(patterns.iter().map(|(pattern, _)| pattern))
+            .map_err(|e| Error::Needle(e.to_string()))?;
+        let overlap = patterns
+            .iter()
+            .map(|(pattern, _)| pattern.len())
+            .max()
+            .unwrap()
+            - 1;
+        Ok(Self {
+            automaton,
+            encodings: patterns
+                .into_iter()
+                .map(|(_, names)| names.into())
+                .collect(),
+            overlap,
+        })
+    }
+
+    /// Searches all `inputs` and hands every hit over to `f`, input by input,
+    /// and within an input in the order the hits end.
+    pub fn search<F>(
+        &self,
+        inputs: Vec<(InputLabel, Input)>,
+        chunk_size: usize,
+        on_input_error: OnInputError,
+        mut f: F,
+    ) -> crate::Result<ScanSummary>
+    where
+        F: FnMut(NeedleHit) -> crate::Result<()>,
+    {
+        let mut summary = ScanSummary::default();
+        for (idx, (input_label, input)) in inputs.into_iter().enumerate() {
+            let input_file_id = idx + 1;
+            let input_label = Arc::new(input_label);
+            if let Err(error) =
+                self.search_input(input, input_file_id, &input_label, chunk_size, &mut f)?
+            {
+                let failed = FailedInput {
+                    input_file_id,
+                    input_label,
+                    error,
+                };
+                match on_input_error {
+                    OnInputError::Abort => return Err(failed.into()),
+                    OnInputError::Continue => summary.failed_inputs.push(failed),
+                }
+            }
+        }
+        Ok(summary)
+    }
+
+    /// Searches one input. The outer error comes from `f`, the inner one
+    /// from opening or reading the input.
+    fn search_input<F>(
+        &self,
+        input: Input,
+        input_file_id: InputFileId,
+        input_label: &Arc<InputLabel>,
+        chunk_size: usize,
+        f: &mut F,
+    ) -> crate::Result<std::io::Result<()>>
+    where
+        F: FnMut(NeedleHit) -> crate::Result<()>,
+    {
+        let mut windows = match Slicer::new(input, Some(input_file_id), chunk_size) {
+            Ok(slicer) => slicer.overlapping(self.overlap),
+            Err(e) => return Ok(Err(e)),
+        };
+        while let Some(window) = windows.next_window() {
+            let window = match window {
+                Ok(window) => window,
+                Err(e) => return Ok(Err(e)),
+            };
+            // Hits ending in the carried bytes were found with the previous
+            // chunk.
+            for m in self.automaton.find_overlapping_iter(window.bytes) {
+                if m.end() <= window.carried {
+                    continue;
+                }
+                f(NeedleHit {
+                    input_file_id: Some(input_file_id),
+                    input_label: input_label.clone(),
+                    position: window.position + m.start() as ByteCounter,
+                    len: m.len(),
+                    encodings: self.encodings[m.pattern()].clone(),
+                })?;
+            }
+        }
+        Ok(Ok(()))
+    }
+}
+
+/// Searched in addition to the encodings of the missions.
+const ALWAYS_SEARCHED: [&Encoding; 3] = [UTF_16LE, UTF_16BE, X_USER_DEFINED];
+
+/// Encodes `needle`, or returns `None` when `encoding` can not represent it.
+fn encode(needle: &str, encoding: &'static Encoding) -> Option<Vec<u8>> {
+    if encoding == UTF_16LE {
+        return Some(needle.encode_utf16().flat_map(u16::to_le_bytes).collect());
+    }
+    if encoding == UTF_16BE {
+        return Some(needle.encode_utf16().flat_map(u16::to_be_bytes).collect());
+    }
+    if encoding == X_USER_DEFINED {
+        // ASCII stays, U+F780..U+F7FF are the bytes 0x80..0xFF.
+        return needle
+            .chars()
+            .map(|c| match c as u32 {
+                cp @ 0..=0x7f => Some(cp as u8),
+                cp @ 0xf780..=0xf7ff => Some((cp - 0xf700) as u8),
+                _ => None,
+            })
+            .collect();
+    }
+    if encoding.output_encoding() != encoding {
+        // The replacement encoding has no encoder.
+        return None;
+    }
+    let mut encoder = encoding.new_encoder();
+    let mut bytes =
+        Vec::with_capacity(encoder.max_buffer_length_from_utf8_without_replacement(needle.len())?);
+    let (result, _) = encoder.encode_from_utf8_to_vec_without_replacement(needle, &mut bytes, true);
+    if result != EncoderResult::InputEmpty {
+        return None;
+    }
+    // The ISO-2022-JP encoder switches back to ASCII at the end. The text
+    // following the needle in the input may not.
+    if bytes.ends_with(b"\x1b(B") && !needle.ends_with(|c: char| c.is_ascii()) {
+        bytes.truncate(bytes.len() - 3);
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MissionConfig;
+    use encoding_rs::UTF_8;
+
+    fn hits(needle: &Needle, bytes: &[u8], chunk_size: usize) -> Vec<(ByteCounter, String)> {
+        let mut hits = Vec::new();
+        needle
+            .search(
+                Input::label_all(vec![Input::Bytes(bytes)]),
+                chunk_size,
+                OnInputError::Abort,
+                |hit| {
+                    hits.push((hit.position, hit.encodings.join("+")));
+                    Ok(())
+                },
+            )
+            .unwrap();
+        hits
+    }
+
+    #[test]
+    fn utf_16_and_x_user_defined_are_always_searched() {
+        let missions = Missions::from_configs(vec![MissionConfig::new(UTF_8)]).unwrap();
+        let needle = Needle::new("kéy", &missions).unwrap();
+        let mut bytes = "kéy ".as_bytes().to_vec();
+        bytes.extend("kéy".encode_utf16().flat_map(u16::to_le_bytes));
+        bytes.push(b' ');
+        bytes.extend("kéy".encode_utf16().flat_map(u16::to_be_bytes));
+        assert_eq!(
+            hits(&needle, &bytes, 0x100),
+            [
+                (0, "UTF-8".to_string()),
+                (5, "UTF-16LE".to_string()),
+                (12, "UTF-16BE".to_string()),
+            ]
+        );
+
+        // Only x-user-defined can encode U+F7E9, as the byte 0xE9.
+        let needle = Needle::new("k\u{f7e9}y", &missions).unwrap();
+        assert_eq!(
+            hits(&needle, b"..k\xe9y", 0x100),
+            [(2, "x-user-defined".to_string())]
+        );
+    }
+
+    #[test]
+    fn hits_straddling_a_window_boundary_are_found_once() {
+        let missions = Missions::from_configs(vec![MissionConfig::new(UTF_8)]).unwrap();
+        let needle = Needle::new("needle", &missions).unwrap();
+        let mut bytes = b"..needle...needleneedle".to_vec();
+        bytes.extend("needle".encode_utf16().flat_map(u16::to_le_bytes));
+        let expected = hits(&needle, &bytes, 0x100);
+        assert_eq!(expected.len(), 4);
+        // Small chunks cut every hit, most of them several times.
+        for chunk_size in 1..=13 {
+            assert_eq!(
+                hits(&needle, &bytes, chunk_size),
+                expected,
+                "{}",
+                chunk_size
+            );
+        }
+    }
+}
@@ -7,6 +7,7 @@ pub const CHARS_MIN_DEFAULT: u8 = 4;
 pub const COUNTER_OFFSET_DEFAULT: ByteCounter = 0;
 pub const OUTPUT_LINE_CHAR_NB_MAX_DEFAULT: usize = 64;
 pub const OUTPUT_LINE_CHAR_NB_MIN: usize = 6;
+pub const SEGMENT_LEN_DEFAULT: usize = 0x400_0000;
 
 #[derive(Debug, Hash, Clone, Eq, PartialEq, Copy)]
 pub enum Radix {
@@ -15,6 +16,17 @@ pub enum Radix {
     D,
 }
 
+impl Radix {
+    /// Formats a byte offset in this radix, without prefix.
+    pub fn format(self, offset: ByteCounter) -> String {
+        match self {
+            Radix::O => format!("{:o}", offset),
+            Radix::X => format!("{:x}", offset),
+            Radix::D => format!("{}", offset),
+        }
+    }
+}
+
 impl FromStr for Radix {
     type Err = String;
     fn from_str(rad: &str) -> Result<Radix, Self::Err> {
@@ -26,3 +38,14 @@ impl FromStr for Radix {
         }
     }
 }
+
+/// What to do when an input can not be opened or read.
+#[derive(Debug, Default, Hash, Clone, Eq, PartialEq, Copy)]
+pub enum OnInputError {
+    /// Stop the scan and return `Error::Input`.
+    #[default]
+    Abort,
+    /// Record the input in `ScanSummary::failed_inputs` and go on with the
+    /// next one. Findings read before the error are kept.
+    Continue,
+}
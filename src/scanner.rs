@@ -13,9 +13,13 @@ pub struct ScannerStates {
 }
 
 impl ScannerStates {
-    pub fn new(missions: Missions) -> Self {
+    /// Scanner states for input that starts `offset` bytes into the input
+    /// stream, e.g. for a segment of a larger input.
+    pub fn new(missions: &Missions, offset: ByteCounter) -> Self {
         let v = missions.v.iter().fold(vec![], |mut acc, m| {
-            acc.push(Arc::new(Mutex::new(ScannerState::new(m.clone()))));
+            let mut ss = ScannerState::new(m.clone());
+            ss.consumed_bytes += offset;
+            acc.push(Arc::new(Mutex::new(ss)));
             acc
         });
         Self { v }
@@ -0,0 +1,440 @@
+//! Distributes the scan work over worker threads.
+//!
+//! Every input is an independent job with its own scanner states, and so is
+//! every segment of a large in-memory input. Jobs are handed out to the
+//! workers in input order. Each job sends its findings through a bounded
+//! channel of its own, which the caller drains job by job. This way findings
+//! arrive in the same order, no matter how many workers there are.
+//!
+//! A job that fails to open or read its input sends the I/O error as its
+//! last message. The caller then either aborts or records the input as
+//! failed, see `OnInputError`.
+
+use crate::{
+    finding::{OwnedFinding, ScanSummary},
+    finding_collection::FindingCollection,
+    group_filter::GroupFilter,
+    input::{ByteCounter, FailedInput, Input, InputFileId, InputLabel, Slicer},
+    mission::Missions,
+    options::OnInputError,
+    scanner::ScannerStates,
+};
+use scoped_threadpool::Pool;
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{self, SyncSender},
+        Arc, Mutex,
+    },
+};
+
+/// Number of zero bytes required on both sides of a segment boundary. No
+/// string and no multi-byte character can cross such a run of zeros in any
+/// of the supported encodings, including UTF-16.
+pub const SEGMENT_BOUNDARY_ZEROS: usize = 4;
+
+/// Capacity, in input chunks, of the channel between a job and the caller.
+const JOB_CHANNEL_BATCHES: usize = 4;
+
+/// One unit of work: an input or a segment of it.
+pub struct Job<'a> {
+    input: Input<'a>,
+    input_file_id: Option<InputFileId>,
+    input_label: Arc<InputLabel>,
+    /// Position of the segment in the input.
+    offset: ByteCounter,
+}
+
+/// Turns the labeled `inputs` into jobs. With `segment_len` set, in-memory
+/// inputs larger than `segment_len` are cut into segments that can be
+/// scanned in parallel.
+pub fn plan(
+    inputs: Vec<(InputLabel, Input)>,
+    segment_len: Option<usize>,
+    chunk_size: usize,
+) -> Vec<Job> {
+    let mut jobs = Vec::with_capacity(inputs.len());
+    for (idx, (input_label, input)) in inputs.into_iter().enumerate() {
+        let input_label = Arc::new(input_label);
+        let input_file_id = Some(idx + 1);
+        match (input, segment_len) {
+            (Input::Bytes(bytes), Some(segment_len)) => {
+                let mut start = 0;
+                while let Some(end) = segment_end(bytes, start, segment_len, chunk_size) {
+                    jobs.push(Job {
+                        input: Input::Bytes(&bytes[start..end]),
+                        input_file_id,
+                        input_label: input_label.clone(),
+                        offset: start as ByteCounter,
+                    });
+                    start = end;
+                }
+                jobs.push(Job {
+                    input: Input::Bytes(&bytes[start..]),
+                    input_file_id,
+                    input_label,
+                    offset: start as ByteCounter,
+                });
+            }
+            (input, _) => jobs.push(Job {
+                input,
+                input_file_id,
+                input_label,
+                offset: 0,
+            }),
+        }
+    }
+    jobs
+}
+
+/// Finds the end of the segment starting at `start`: the first even chunk
+/// boundary at least `segment_len` bytes after `start`, that is surrounded
+/// by `SEGMENT_BOUNDARY_ZEROS` zero bytes on both sides.
+///
+/// Cutting at such a boundary does not change the scan result: the
+/// decoders are in their initial state after a zero, no string is cut, and
+/// as the boundary is a chunk boundary, the positions of the findings stay
+/// the same.
+fn segment_end(bytes: &[u8], start: usize, segment_len: usize, chunk_size: usize) -> Option<usize> {
+    // `ScannerBuilder` keeps `segment_len` from undercutting the zeros
+    // before the first boundary.
+    debug_assert!(segment_len >= SEGMENT_BOUNDARY_ZEROS);
+    let min_end = start.checked_add(segment_len)?;
+    let mut end = min_end.div_ceil(chunk_size).checked_mul(chunk_size)?;
+    while end.checked_add(SEGMENT_BOUNDARY_ZEROS)? <= bytes.len() {
+        if end.is_multiple_of(2)
+            && bytes[end - SEGMENT_BOUNDARY_ZEROS..end + SEGMENT_BOUNDARY_ZEROS]
+                .iter()
+                .all(|&b| b == 0)
+        {
+            return Some(end);
+        }
+        end = end.checked_add(chunk_size)?;
+    }
+    None
+}
+
+/// What a job sends to the caller.
+type Batch = io::Result<Vec<OwnedFinding>>;
+
+/// Scans all `jobs` with up to `workers` threads and hands the findings over
+/// to `f` in input order. When `f` returns an error, or an input fails and
+/// `on_input_error` is `Abort`, the workers are stopped and the error is
+/// returned.
+pub fn run<F>(
+    jobs: Vec<Job>,
+    missions: &Missions,
+    chunk_size: usize,
+    workers: usize,
+    on_input_error: OnInputError,
+    mut f: F,
+) -> crate::Result<ScanSummary>
+where
+    F: FnMut(OwnedFinding) -> crate::Result<()>,
+{
+    let stop = AtomicBool::new(false);
+    let mut receivers = Vec::with_capacity(jobs.len());
+    let mut queue = VecDeque::with_capacity(jobs.len());
+    for job in jobs {
+        let (tx, rx) = mpsc::sync_channel::<Batch>(JOB_CHANNEL_BATCHES);
+        receivers.push((rx, job.input_file_id, job.input_label.clone()));
+        queue.push_back((job, tx));
+    }
+    let n_workers = workers.min(queue.len());
+    let queue = Mutex::new(queue);
+
+    std::thread::scope(|s| {
+        for _ in 0..n_workers {
+            s.spawn(|| {
+                // With several missions, every input chunk is scanned by
+                // all missions in parallel.
+                let mut pool = (missions.len() > 1).then(|| Pool::new(missions.len() as u32));
+                while !stop.load(Ordering::Relaxed) {
+                    let next = queue.lock().unwrap().pop_front();
+                    match next {
+                        Some((job, tx)) => {
+                            run_job(job, missions, chunk_size, pool.as_mut(), &tx, &stop)
+                        }
+                        None => break,
+                    }
+                }
+            });
+        }
+
+        // The receivers are dropped on return. This unblocks all workers
+        // still sending.
+        let result = (|| {
+            let mut summary = ScanSummary::default();
+            for (rx, input_file_id, input_label) in receivers {
+                for batch in rx.iter() {
+                    match batch {
+                        Ok(findings) => {
+                            for finding in findings {
+                                f(finding)?;
+                            }
+                        }
+                        Err(error) => {
+                            let failed = FailedInput {
+                                input_file_id: input_file_id.unwrap_or_default(),
+                                input_label: input_label.clone(),
+                                error,
+                            };
+                            match on_input_error {
+                                OnInputError::Abort => return Err(failed.into()),
+                                OnInputError::Continue => summary.failed_inputs.push(failed),
+                            }
+                        }
+                    }
+                }
+            }
+            Ok(summary)
+        })();
+        if result.is_err() {
+            stop.store(true, Ordering::Relaxed);
+        }
+        result
+    })
+}
+
+fn run_job(
+    job: Job,
+    missions: &Missions,
+    chunk_size: usize,
+    mut pool: Option<&mut Pool>,
+    tx: &SyncSender<Batch>,
+    stop: &AtomicBool,
+) {
+    // Every job starts with fresh decoders.
+    let scanner_states = ScannerStates::new(missions, job.offset);
+    let mut group_filter = GroupFilter::new(missions);
+    let slicer = match Slicer::new(job.input, job.input_file_id, chunk_size) {
+        Ok(slicer) => slicer,
+        Err(e) => {
+            let _ = tx.send(Err(e));
+            return;
+        }
+    };
+    for slice in slicer {
+        if stop.load(Ordering::Relaxed) {
+            return;
+        }
+        let (slice, input_file_id, is_last_input_buffer) = match slice {
+            Ok(slice) => slice,
+            Err(e) => {
+                // Hand out the held back findings before the error.
+                if let Some(group_filter) = group_filter.as_mut() {
+                    let batch = group_filter.filter(Vec::new(), true);
+                    if !batch.is_empty() {
+                        let _ = tx.send(Ok(batch));
+                    }
+                }
+                let _ = tx.send(Err(e));
+                return;
+            }
+        };
+        let mut batch = scan_chunk(
+            &scanner_states,
+            pool.as_deref_mut(),
+            input_file_id,
+            &job.input_label,
+            &slice,
+            is_last_input_buffer,
+        );
+        if let Some(group_filter) = group_filter.as_mut() {
+            batch = group_filter.filter(batch, is_last_input_buffer);
+        }
+        if !batch.is_empty() && tx.send(Ok(batch)).is_err() {
+            stop.store(true, Ordering::Relaxed);
+            return;
+        }
+    }
+}
+
+/// Scans one input chunk with all missions, in parallel when a `pool` is
+/// given, and merges the findings by position.
+pub fn scan_chunk(
+    scanner_states: &ScannerStates,
+    pool: Option<&mut Pool>,
+    input_file_id: Option<InputFileId>,
+    input_label: &Arc<InputLabel>,
+    input_buffer: &[u8],
+    is_last_input_buffer: bool,
+) -> Vec<OwnedFinding> {
+    let mut results: Vec<Option<Pin<Box<FindingCollection>>>> =
+        scanner_states.iter().map(|_| None).collect();
+    match pool {
+        Some(pool) => pool.scoped(|scope| {
+            for (ss, result) in scanner_states.iter().zip(results.iter_mut()) {
+                scope.execute(move || {
+                    *result = Some(FindingCollection::from(
+                        ss.clone(),
+                        input_file_id,
+                        input_buffer,
+                        is_last_input_buffer,
+                    ));
+                });
+            }
+        }),
+        None => {
+            for (ss, result) in scanner_states.iter().zip(results.iter_mut()) {
+                *result = Some(FindingCollection::from(
+                    ss.clone(),
+                    input_file_id,
+                    input_buffer,
+                    is_last_input_buffer,
+                ));
+            }
+        }
+    }
+    let results: Vec<Pin<Box<FindingCollection>>> = results.into_iter().flatten().collect();
+    itertools::kmerge(&results)
+        .map(|finding| OwnedFinding::new(finding, input_label.clone()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mission::MissionConfig;
+    use encoding_rs::{UTF_16LE, UTF_8};
+
+    const CHUNK_SIZE: usize = 16;
+    const SEGMENT_LEN: usize = 48;
+
+    /// Words separated by runs of 1 to 11 zero bytes, some of them UTF-16LE
+    /// encoded. Returns the input and the UTF-8 words.
+    fn input(seed: u32, len: usize) -> (Vec<u8>, Vec<String>) {
+        let mut state = seed;
+        let mut random = move |n: u32| {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state % n
+        };
+        let mut bytes = Vec::new();
+        let mut words = Vec::new();
+        while bytes.len() < len {
+            let word: String = (0..4 + random(20))
+                .map(|_| (b'a' + random(26) as u8) as char)
+                .collect();
+            if random(3) == 0 {
+                bytes.extend(word.encode_utf16().flat_map(u16::to_le_bytes));
+            } else {
+                words.push(word.clone());
+                bytes.extend_from_slice(word.as_bytes());
+            }
+            bytes.resize(bytes.len() + 1 + random(11) as usize, 0);
+        }
+        (bytes, words)
+    }
+
+    fn missions() -> Missions {
+        Missions::from_configs(vec![
+            MissionConfig::new(UTF_8),
+            MissionConfig::new(UTF_16LE),
+        ])
+        .unwrap()
+    }
+
+    type Row = (Option<InputFileId>, ByteCounter, usize, String, bool);
+
+    fn scan(inputs: &[&[u8]], segment_len: Option<usize>, workers: usize) -> Vec<Row> {
+        let missions = missions();
+        let inputs = inputs.iter().map(|bytes| Input::Bytes(bytes)).collect();
+        let jobs = plan(Input::label_all(inputs), segment_len, CHUNK_SIZE);
+        let mut rows = Vec::new();
+        run(
+            jobs,
+            &missions,
+            CHUNK_SIZE,
+            workers,
+            OnInputError::Abort,
+            |finding| {
+                rows.push((
+                    finding.input_file_id,
+                    finding.position,
+                    finding.mission.mission_id,
+                    finding.s,
+                    finding.s_completes_previous_s,
+                ));
+                Ok(())
+            },
+        )
+        .unwrap();
+        rows
+    }
+
+    #[test]
+    fn segments_are_cut_at_chunk_boundaries_between_zeros() {
+        let (bytes, _) = input(1, 4096);
+        let inputs = Input::label_all(vec![Input::Bytes(&bytes)]);
+        let jobs = plan(inputs, Some(SEGMENT_LEN), CHUNK_SIZE);
+        assert!(jobs.len() > 10);
+        for job in &jobs[1..] {
+            let offset = job.offset as usize;
+            assert_eq!(offset % CHUNK_SIZE, 0);
+            assert!(
+                bytes[offset - SEGMENT_BOUNDARY_ZEROS..offset + SEGMENT_BOUNDARY_ZEROS]
+                    .iter()
+                    .all(|&b| b == 0)
+            );
+        }
+        let len: usize = jobs
+            .iter()
+            .map(|job| match job.input {
+                Input::Bytes(bytes) => bytes.len(),
+                _ => unreachable!(),
+            })
+            .sum();
+        assert_eq!(len, bytes.len());
+    }
+
+    #[test]
+    fn segment_ends_do_not_overflow() {
+        let bytes = [0u8; 64];
+        for (segment_len, chunk_size) in [
+            (SEGMENT_BOUNDARY_ZEROS, usize::MAX),
+            (SEGMENT_BOUNDARY_ZEROS, 1 << (usize::BITS - 1)),
+            (usize::MAX, 1),
+        ] {
+            assert_eq!(segment_end(&bytes, 0, segment_len, chunk_size), None);
+            assert_eq!(segment_end(&bytes, 32, segment_len, chunk_size), None);
+        }
+        assert_eq!(segment_end(&bytes, 0, SEGMENT_BOUNDARY_ZEROS, 1), Some(4));
+        assert_eq!(segment_end(&bytes, 56, SEGMENT_BOUNDARY_ZEROS, 1), Some(60));
+        assert_eq!(segment_end(&bytes, 57, SEGMENT_BOUNDARY_ZEROS, 1), None);
+    }
+
+    #[test]
+    fn segments_and_workers_do_not_change_the_findings() {
+        let (first, _) = input(2, 4096);
+        let (second, _) = input(3, 1000);
+        let inputs: &[&[u8]] = &[&first, &second];
+        let expected = scan(inputs, None, 1);
+        assert!(!expected.is_empty());
+        for workers in [1, 2, 7] {
+            assert_eq!(scan(inputs, Some(SEGMENT_LEN), workers), expected);
+        }
+    }
+
+    #[test]
+    fn strings_at_segment_cuts_are_found_once() {
+        let (bytes, words) = input(4, 4096);
+        for workers in [1, 7] {
+            let found: Vec<String> = scan(&[&bytes], Some(SEGMENT_LEN), workers)
+                .into_iter()
+                .filter(|(_, _, mission_id, _, _)| *mission_id == 0)
+                .fold(Vec::new(), |mut found, (_, _, _, s, continues)| {
+                    match found.last_mut() {
+                        Some(last) if continues => last.push_str(&s),
+                        _ => found.push(s),
+                    }
+                    found
+                });
+            assert_eq!(found, words);
+        }
+    }
+}
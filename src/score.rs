@@ -0,0 +1,384 @@
+//! Plausibility scoring of findings.
+//!
+//! Dense encodings like UTF-16 decode almost any byte pair to some
+//! character, so binary data yields long "strings" of characters from
+//! unrelated scripts. The filters only see one character at a time. The
+//! score looks at the whole string instead, and rates how much it resembles
+//! text, from 0 (noise) to 1 (text). It combines four measures:
+//!
+//! - character class transitions: letters next to symbols, or letters of
+//!   one script next to letters of another, are rare in text,
+//! - script consistency: the share of letters of the dominant script,
+//! - n-gram frequencies: the share of letter pairs (Latin, Cyrillic, Greek,
+//!   Arabic), or characters (Han, Hangul), among the most frequent ones of
+//!   common languages in that script,
+//! - rare code points: the share of characters that are unassigned, for
+//!   private use, or from rarely used scripts and blocks.
+//!
+//! The measures are heuristics. Short strings get less reliable scores.
+
+use crate::script::unassigned;
+use std::{collections::HashSet, sync::OnceLock};
+use unicode_script::{Script, UnicodeScript};
+
+/// Weights of transitions, script consistency, n-grams and rare code points.
+const WEIGHTS: [f32; 4] = [0.25, 0.25, 0.3, 0.2];
+
+/// Share of letter pairs, or characters, in the frequency tables at which
+/// the n-gram measure reaches 1. Typical text is above.
+const NGRAM_RATIO_TEXT: f32 = 0.6;
+
+/// Frequent letter pairs of English, German, French, Spanish, Italian and
+/// Portuguese, lowercase.
+const LATIN_BIGRAMS: &str = "th he in er an re on at en nd ti es or te of ed is it al ar st to \
+     nt ng se ha as ou io le ve co me de hi ri ro ic ne ea ra ce li ch ll be ma si om ur ca el \
+     ta la ns di fo ho pe ec pr no ct us ac ot il tr ly nc et ut ss so rs un lo wa ge ie wh ee \
+     wi em ad ol rt po we na ul ni ts mo ow pa im mi ai sh ir su id os iv ia am fi ci vi pl ig \
+     tu ev ld ry mp fe bl ab gh ty op wo sa ay ex ke fr oo av ag if ap gr od bo sp rd do uc bu \
+     ei ov by rm ep tt oc fa ef cu rn sc gi da yo cr cl du ga qu ue ff ba ey ls va um pp ua up \
+     lu go ht ru ug ds lt pi rc rr eg au ck ew mu br bi pt ak pu ui rg ib tl ny ki rk ys ob mm \
+     fu ph og ms ye ud mb ip ub oi rl gu dr sch ung eu zu nn gl gn zi ao oe";
+
+/// Frequent letter pairs of Russian, Ukrainian and Bulgarian, lowercase.
+const CYRILLIC_BIGRAMS: &str = "ст но то на ен ов ни ра во ко ал пр ро ре ос по ер не ли от ка \
+     ва го ор ан ла ет ть ол ил те ел ин ат ны ом од да ис ле ри ак ит ми ог ве ас ой ес ки ся \
+     ед ав ие тр ем мо ам ль ск че ди ру ма та за ей ым ых ий ую ая ее ия де до вы ме бы ту ча \
+     из ез зн ык им ти сл ду вс лю ше уч бо су тв ув пе се чт ье ьн лы сь ци си мн со оз ож уд \
+     ба ві ні ць ня рі";
+
+/// Frequent letter pairs of Greek, lowercase.
+const GREEK_BIGRAMS: &str = "το τα κα αι ου ει ον ης ην ια ερ ασ ατ ετ στ ντ πο απ με να εν ισ \
+     ικ ολ αν ορ ρα ρο ος ας ες τη τι νο λο πα πρ συ επ ιο ησ αλ ελ ιτ τε μα μο λα ση σε κο ων \
+     ως";
+
+/// Frequent letter pairs of Arabic and Persian.
+const ARABIC_BIGRAMS: &str = "ال لا ين من في ها ية ون ات ان ما را ير ري ار لم ام لى عل ته ني وا \
+     يا ور ند وم قا مل لي له بي تا سا كا ست نا دا ذا هم ول لت يد اس فا ده دي رد اب اي اد ای ین \
+     ری";
+
+/// Frequent Chinese and Japanese characters.
+const HAN_CHARS: &str =
+    "的一是不了人我在有他这中大来上国个到说们为子和你地出道也时年得就那要下以生会自着\
+     去之过家学对可她里后小么心多天而能好都然没日于起还发成事只作当想看文无开手十用主\
+     行方又如前所本见经头面公同三已老从动两长知民样现分将外但身些与高意进把法此实回二\
+     理美点月明其种声全工己话儿者向情部正名定女问力机给等几很业最间新什打便位因重被走\
+     电四第门相次东政海口使教西再平真听世气信北少关并内加化由却代军产入先山五太水万市\
+     眼体别处总才场师书比住员九笑性通目华报立马命张活难神数件安表原车白应路期叫死常提\
+     感金何更反合放做系计或司利受光王果亲界及今京务制解各任至清物台象记边共风战干接它\
+     许八特觉望直服毛林题建南度统色字请交爱让认算论百吃义科怎元社术结六功指思非流每青\
+     管夫连远资队跟带花快条院变联言权往展该领传近留红治决周保达办运武半候七必城父强步\
+     完革深区即求品士转量空甚众技轻程告江语英基派满式李息写呢识极令黄德收脸钱党倒未持\
+     取设始版双历越史商千片容研像找友孩站广改议形委早房音火际则首单据导影失拿网香似斯\
+     专石若兵弟谁校志飞观争究包组造落视济喜离虽坐集编宝谈府拉黑且随格尽讲布杀微怕母调\
+     局根曾准团段终乐切级克精哪官示冷域們這個來為說會國時於過對後學動發當無開點現見經\
+     種話長問門體還關電機東車書區樣實總與義語氣聽進應間員頭萬";
+
+/// Frequent Korean syllables.
+const HANGUL_CHARS: &str =
+    "이의에는을가하고지다서기사로한리자도인대부수어나정국일시전있보해적아라그연여것학\
+     제구장소내들유주상우마스계원면관문경동성신실미세니만위방개트회무선금화요거비업공\
+     식발물터합간재분타데모영통생과저없러진조운당련중작된할습으를와";
+
+/// The measures that are defined for `s`, each from 0 to 1, see the module
+/// description. The n-gram measure is `None` without letters of a script
+/// with a frequency table, all are `None` for an empty string.
+fn measures(s: &str) -> [Option<f32>; 4] {
+    let mut chars = 0usize;
+    let mut pairs = 0usize;
+    let mut odd_pairs = 0usize;
+    let mut letters_by_family: Vec<(Family, usize)> = Vec::new();
+    let mut ngrams = 0usize;
+    let mut frequent_ngrams = 0usize;
+    let mut rare = 0usize;
+    let mut prev: Option<(char, Class)> = None;
+    for c in s.chars() {
+        chars += 1;
+        let class = Class::of(c);
+        if is_rare(c) {
+            rare += 1;
+        }
+        if let Class::Letter(family) = class {
+            match letters_by_family.iter_mut().find(|(f, _)| *f == family) {
+                Some((_, n)) => *n += 1,
+                None => letters_by_family.push((family, 1)),
+            }
+            match family {
+                Family::Han | Family::Hangul => {
+                    ngrams += 1;
+                    // All Kana are frequent.
+                    let is_frequent = match c.script() {
+                        Script::Han => han_chars().contains(&c),
+                        Script::Hangul => hangul_chars().contains(&c),
+                        _ => true,
+                    };
+                    if is_frequent {
+                        frequent_ngrams += 1;
+                    }
+                }
+                _ => {
+                    if let (Some((p, Class::Letter(prev_family))), Some(table)) =
+                        (prev, bigrams(family))
+                    {
+                        if prev_family == family {
+                            ngrams += 1;
+                            if table.contains(&(lowercase(p), lowercase(c))) {
+                                frequent_ngrams += 1;
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        if let Some((_, prev_class)) = prev {
+            pairs += 1;
+            if prev_class.is_odd_next_to(class) {
+                odd_pairs += 1;
+            }
+        }
+        prev = Some((c, class));
+    }
+    if chars == 0 {
+        return [None; 4];
+    }
+    let ratio = |n: usize, total: usize| n as f32 / total as f32;
+    let transitions = if pairs == 0 {
+        1.0
+    } else {
+        1.0 - ratio(odd_pairs, pairs)
+    };
+    let letters: usize = letters_by_family.iter().map(|(_, n)| n).sum();
+    let script_consistency = match letters_by_family.iter().map(|(_, n)| *n).max() {
+        Some(dominant) => ratio(dominant, letters),
+        None => 1.0,
+    };
+    // Squared, because random characters hit the tables, too.
+    let ngram = (ngrams > 0).then(|| {
+        (ratio(frequent_ngrams, ngrams) / NGRAM_RATIO_TEXT)
+            .min(1.0)
+            .powi(2)
+    });
+    let rare_code_points = 1.0 - (2.0 * ratio(rare, chars)).min(1.0);
+    [
+        Some(transitions),
+        Some(script_consistency),
+        ngram,
+        Some(rare_code_points),
+    ]
+}
+
+/// Rates how much `s` resembles natural language text, from 0 (noise) to
+/// 1 (text). An empty string scores 0.
+///
+/// The score is the weighted geometric mean of the measures, so one measure
+/// close to 0 is enough for a low score.
+pub fn plausibility(s: &str) -> f32 {
+    let (log_sum, weights) = measures(s)
+        .iter()
+        .zip(WEIGHTS)
+        .filter_map(|(measure, weight)| {
+            measure.map(|m| (m.max(f32::EPSILON).ln() * weight, weight))
+        })
+        .fold((0.0, 0.0), |(log_sum, weights), (m, w)| {
+            (log_sum + m, weights + w)
+        });
+    if weights == 0.0 {
+        0.0
+    } else {
+        (log_sum / weights).exp()
+    }
+}
+
+/// Scripts that are written together count as one, e.g. Japanese mixes Han
+/// and Kana.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Family {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Han,
+    Hangul,
+    Other(Script),
+}
+
+impl Family {
+    /// The family of a letter, `None` for other characters.
+    pub(crate) fn of(c: char) -> Option<Self> {
+        c.is_alphabetic().then(|| match c.script() {
+            Script::Latin => Family::Latin,
+            Script::Cyrillic => Family::Cyrillic,
+            Script::Greek => Family::Greek,
+            Script::Arabic => Family::Arabic,
+            Script::Han | Script::Hiragana | Script::Katakana | Script::Bopomofo => Family::Han,
+            Script::Hangul => Family::Hangul,
+            script => Family::Other(script),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    Letter(Family),
+    Digit,
+    Space,
+    Punctuation,
+    Symbol,
+}
+
+impl Class {
+    fn of(c: char) -> Self {
+        if let Some(family) = Family::of(c) {
+            Class::Letter(family)
+        } else if c.is_numeric() {
+            Class::Digit
+        } else if c.is_whitespace() {
+            Class::Space
+        } else if c.is_ascii_punctuation()
+            || matches!(c, '\u{a1}'..='\u{bf}' | '\u{2010}'..='\u{205e}' | '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff0f}' | '\u{ff1a}'..='\u{ff20}')
+        {
+            Class::Punctuation
+        } else {
+            Class::Symbol
+        }
+    }
+
+    /// Is a character of class `next` unusual after one of `self`, or vice
+    /// versa?
+    fn is_odd_next_to(self, next: Class) -> bool {
+        match (self, next) {
+            (Class::Letter(a), Class::Letter(b)) => a != b,
+            (Class::Symbol, Class::Letter(_) | Class::Digit)
+            | (Class::Letter(_) | Class::Digit, Class::Symbol) => true,
+            _ => false,
+        }
+    }
+}
+
+/// Unassigned, private use, or from a script or block rarely used in
+/// today's text.
+fn is_rare(c: char) -> bool {
+    let cp = c as u32;
+    if unassigned().pass(cp)
+        || matches!(cp, 0xe000..=0xf8ff | 0xf_0000..=0x10_ffff)
+        || c.is_control() && !c.is_whitespace()
+    {
+        return true;
+    }
+    match c.script() {
+        // Only the Unified Ideographs block is in everyday use.
+        Script::Han => !(0x4e00..=0x9fff).contains(&cp) && !(0x3000..=0x303f).contains(&cp),
+        Script::Common | Script::Inherited => cp >= 0x1_0000,
+        script => !COMMON_SCRIPTS.contains(&script),
+    }
+}
+
+/// Scripts of languages with many speakers or much digital text.
+const COMMON_SCRIPTS: [Script; 22] = [
+    Script::Latin,
+    Script::Greek,
+    Script::Cyrillic,
+    Script::Armenian,
+    Script::Hebrew,
+    Script::Arabic,
+    Script::Devanagari,
+    Script::Bengali,
+    Script::Gurmukhi,
+    Script::Gujarati,
+    Script::Tamil,
+    Script::Telugu,
+    Script::Kannada,
+    Script::Malayalam,
+    Script::Thai,
+    Script::Georgian,
+    Script::Hangul,
+    Script::Hiragana,
+    Script::Katakana,
+    Script::Ethiopic,
+    Script::Khmer,
+    Script::Sinhala,
+];
+
+pub(crate) fn lowercase(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
+
+fn bigrams(family: Family) -> Option<&'static HashSet<(char, char)>> {
+    static LATIN: OnceLock<HashSet<(char, char)>> = OnceLock::new();
+    static CYRILLIC: OnceLock<HashSet<(char, char)>> = OnceLock::new();
+    static GREEK: OnceLock<HashSet<(char, char)>> = OnceLock::new();
+    static ARABIC: OnceLock<HashSet<(char, char)>> = OnceLock::new();
+    let (cell, table) = match family {
+        Family::Latin => (&LATIN, LATIN_BIGRAMS),
+        Family::Cyrillic => (&CYRILLIC, CYRILLIC_BIGRAMS),
+        Family::Greek => (&GREEK, GREEK_BIGRAMS),
+        Family::Arabic => (&ARABIC, ARABIC_BIGRAMS),
+        _ => return None,
+    };
+    Some(cell.get_or_init(|| {
+        table
+            .split_whitespace()
+            .flat_map(|ngram| {
+                let chars: Vec<char> = ngram.chars().collect();
+                chars.windows(2).map(|w| (w[0], w[1])).collect::<Vec<_>>()
+            })
+            .collect()
+    }))
+}
+
+pub(crate) fn han_chars() -> &'static HashSet<char> {
+    static HAN: OnceLock<HashSet<char>> = OnceLock::new();
+    HAN.get_or_init(|| HAN_CHARS.chars().filter(|c| !c.is_whitespace()).collect())
+}
+
+pub(crate) fn hangul_chars() -> &'static HashSet<char> {
+    static HANGUL: OnceLock<HashSet<char>> = OnceLock::new();
+    HANGUL.get_or_init(|| {
+        HANGUL_CHARS
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .collect()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::UTF_16LE;
+
+    /// `n` pseudo-random bytes decoded as UTF-16LE.
+    fn utf_16_noise(seed: u64, n: usize) -> String {
+        let mut state = seed;
+        let bytes: Vec<u8> = (0..n)
+            .map(|_| {
+                // xorshift64
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        UTF_16LE.decode_without_bom_handling(&bytes).0.into_owned()
+    }
+
+    #[test]
+    fn text_scores_high() {
+        let texts = [
+            "The quick brown fox jumps over the lazy dog near the river bank.",
+            "Москва является столицей России и крупнейшим городом страны.",
+            "我们在这个国家的大学里学习中文和历史。",
+        ];
+        for text in texts {
+            let score = plausibility(text);
+            assert!(score > 0.5, "{}: {}", text, score);
+        }
+    }
+
+    #[test]
+    fn utf_16_noise_scores_low() {
+        for seed in 1..=100 {
+            let noise = utf_16_noise(seed, 2 * (8 + seed as usize % 32));
+            let score = plausibility(&noise);
+            assert!(score < 0.5, "{:?}: {}", noise, score);
+        }
+    }
+}
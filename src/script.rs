@@ -0,0 +1,288 @@
+//! Filtering of multi-byte characters by their Unicode Script property.
+//!
+//! The Unicode block filter decides by the UTF-8 leading byte alone, so
+//! each of its bits covers a range of 64 or 4096 code points. A
+//! `ScriptFilter` decides per code point instead, e.g. it tells Han from
+//! Hangul. To keep `SplitStr` fast, every filter holds a bitmap of the
+//! accepted code points in the Basic Multilingual Plane, and a short
+//! sorted list of accepted ranges beyond.
+//!
+//! The same representation serves `unassigned()`, the code points that no
+//! printable string contains.
+
+use crate::error::Error;
+use std::{fmt, sync::OnceLock};
+pub use unicode_script::Script;
+use unicode_script::UnicodeScript;
+
+/// Number of code points in the Basic Multilingual Plane.
+const BMP_LEN: u32 = 0x1_0000;
+
+/// Accepts multi-byte characters of the given scripts only. Replaces the
+/// Unicode block filter of a mission when set.
+///
+/// Note that punctuation and digits shared by several scripts belong to the
+/// `Common` script. Add it to keep e.g. Japanese sentences in one piece.
+#[derive(Clone)]
+pub struct ScriptFilter {
+    scripts: Vec<Script>,
+    /// One bit per BMP code point.
+    bmp: Box<[u64]>,
+    /// Accepted code points beyond the BMP, as sorted, disjoint, inclusive
+    /// ranges.
+    supplementary: Box<[(u32, u32)]>,
+}
+
+impl ScriptFilter {
+    pub fn new<I: IntoIterator<Item = Script>>(scripts: I) -> Self {
+        let mut scripts: Vec<Script> = scripts.into_iter().collect();
+        scripts.sort_by_key(|&script| script as u8);
+        scripts.dedup();
+        let mut bmp = vec![0u64; (BMP_LEN / 64) as usize].into_boxed_slice();
+        let mut supplementary: Vec<(u32, u32)> = Vec::new();
+        for &(first, last, script) in script_runs() {
+            if !scripts.contains(&script) {
+                continue;
+            }
+            for cp in first..=last.min(BMP_LEN - 1) {
+                bmp[(cp / 64) as usize] |= 1 << (cp % 64);
+            }
+            if last >= BMP_LEN {
+                let first = first.max(BMP_LEN);
+                match supplementary.last_mut() {
+                    Some((_, prev_last)) if *prev_last + 1 == first => *prev_last = last,
+                    _ => supplementary.push((first, last)),
+                }
+            }
+        }
+        Self {
+            scripts,
+            bmp,
+            supplementary: supplementary.into_boxed_slice(),
+        }
+    }
+
+    /// Parses script names separated by `+`, e.g. `Han+Hiragana+Katakana`.
+    /// Full names and four letter ISO 15924 codes, e.g. `Hani`, are
+    /// accepted, in any case.
+    pub fn parse(names: &str) -> crate::Result<Self> {
+        let scripts = names
+            .split('+')
+            .map(|name| {
+                let name = name.trim();
+                all_scripts()
+                    .iter()
+                    .copied()
+                    .find(|script| {
+                        script.full_name().eq_ignore_ascii_case(name)
+                            || script.short_name().eq_ignore_ascii_case(name)
+                    })
+                    .ok_or_else(|| Error::Script(name.to_string()))
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(Self::new(scripts))
+    }
+
+    pub fn scripts(&self) -> &[Script] {
+        &self.scripts
+    }
+
+    /// Is the character with code point `cp` of one of the scripts?
+    #[inline]
+    pub fn pass(&self, cp: u32) -> bool {
+        if cp < BMP_LEN {
+            self.bmp[(cp / 64) as usize] & (1 << (cp % 64)) != 0
+        } else {
+            self.supplementary
+                .binary_search_by(|&(first, last)| {
+                    if last < cp {
+                        std::cmp::Ordering::Less
+                    } else if first > cp {
+                        std::cmp::Ordering::Greater
+                    } else {
+                        std::cmp::Ordering::Equal
+                    }
+                })
+                .is_ok()
+        }
+    }
+}
+
+impl fmt::Debug for ScriptFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ScriptFilter").field(&self.scripts).finish()
+    }
+}
+
+impl fmt::Display for ScriptFilter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (idx, script) in self.scripts.iter().enumerate() {
+            if idx > 0 {
+                f.write_str("+")?;
+            }
+            f.write_str(script.full_name())?;
+        }
+        Ok(())
+    }
+}
+
+/// Code points that are unassigned or non-characters, i.e. U+FDD0..U+FDEF
+/// and the last two code points of every plane, U+xFFFE and U+xFFFF. These
+/// are the code points of the `Unknown` script, except the private use
+/// areas. Surrogates never reach the filters, the decoders report them as
+/// malformed.
+pub(crate) fn unassigned() -> &'static ScriptFilter {
+    static UNASSIGNED: OnceLock<ScriptFilter> = OnceLock::new();
+    UNASSIGNED.get_or_init(|| {
+        let mut filter = ScriptFilter::new([Script::Unknown]);
+        for cp in 0xe000..=0xf8ff {
+            filter.bmp[cp / 64] &= !(1 << (cp % 64));
+        }
+        let private_use = [(0xf_0000, 0xf_fffd), (0x10_0000, 0x10_fffd)];
+        let mut supplementary = Vec::new();
+        for &(mut first, last) in filter.supplementary.iter() {
+            for &(pu_first, pu_last) in &private_use {
+                if first <= pu_last && pu_first <= last {
+                    if first < pu_first {
+                        supplementary.push((first, pu_first - 1));
+                    }
+                    first = pu_last + 1;
+                }
+            }
+            if first <= last {
+                supplementary.push((first, last));
+            }
+        }
+        filter.supplementary = supplementary.into_boxed_slice();
+        filter
+    })
+}
+
+/// All scripts with at least one code point, in order of appearance.
+pub fn all_scripts() -> &'static [Script] {
+    static SCRIPTS: OnceLock<Vec<Script>> = OnceLock::new();
+    SCRIPTS.get_or_init(|| {
+        let mut scripts: Vec<Script> = Vec::new();
+        for &(_, _, script) in script_runs() {
+            if !scripts.contains(&script) {
+                scripts.push(script);
+            }
+        }
+        scripts
+    })
+}
+
+/// The Script property of all code points, as runs of code points with the
+/// same script. Computed once, on first use.
+fn script_runs() -> &'static [(u32, u32, Script)] {
+    static RUNS: OnceLock<Vec<(u32, u32, Script)>> = OnceLock::new();
+    RUNS.get_or_init(|| {
+        let mut runs: Vec<(u32, u32, Script)> = Vec::new();
+        for c in (0..=char::MAX as u32).filter_map(char::from_u32) {
+            let cp = c as u32;
+            let script = c.script();
+            match runs.last_mut() {
+                Some((_, last, s)) if *s == script && *last + 1 == cp => *last = cp,
+                _ => runs.push((cp, cp, script)),
+            }
+        }
+        runs
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MissionConfig, StringsScanner};
+    use encoding_rs::UTF_8;
+
+    #[test]
+    fn names_and_iso_15924_codes_are_parsed() {
+        let filter = ScriptFilter::parse("hani+HANGUL + Latn").unwrap();
+        // Sorted by the `Script` discriminant.
+        assert_eq!(
+            filter.scripts(),
+            [Script::Hangul, Script::Han, Script::Latin]
+        );
+        assert_eq!(filter.to_string(), "Hangul+Han+Latin");
+        assert!(matches!(
+            ScriptFilter::parse("Han+Klingon"),
+            Err(Error::Script(name)) if name == "Klingon"
+        ));
+        assert!(matches!(ScriptFilter::parse(""), Err(Error::Script(name)) if name.is_empty()));
+    }
+
+    #[test]
+    fn code_points_pass_by_script() {
+        let han = ScriptFilter::parse("Hani").unwrap();
+        let hangul = ScriptFilter::parse("Hang").unwrap();
+        for (c, is_han, is_hangul) in [
+            ('中', true, false),
+            ('한', false, true),
+            ('ㄱ', false, true),
+            ('a', false, false),
+            // CJK Unified Ideographs Extension B, beyond the BMP.
+            ('\u{20000}', true, false),
+            ('\u{2a6df}', true, false),
+            ('\u{30000}', true, false),
+            ('\u{1f600}', false, false),
+        ] {
+            assert_eq!(han.pass(c as u32), is_han, "{:?}", c);
+            assert_eq!(hangul.pass(c as u32), is_hangul, "{:?}", c);
+        }
+        // Unassigned gaps between the ranges of Han.
+        for cp in [0x1_ffff, 0x2_a6e0, 0x2_fffe, 0x10_ffff] {
+            assert!(!han.pass(cp), "U+{:X}", cp);
+        }
+    }
+
+    #[test]
+    fn han_and_hangul_are_told_apart_in_one_buffer() {
+        let scan = |names| {
+            let mission = MissionConfig::new(UTF_8)
+                .chars_min(2)
+                .script_filter(Some(ScriptFilter::parse(names).unwrap()));
+            let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+            let bytes = "\0中文한국어\u{20000}\u{20001}\0".as_bytes();
+            let findings = scanner.scan_bytes(bytes).unwrap().findings;
+            findings
+                .into_iter()
+                .map(|finding| finding.s)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(scan("Han"), ["中文", "\u{20000}\u{20001}"]);
+        assert_eq!(scan("Hangul"), ["한국어"]);
+        assert_eq!(scan("Han+Hangul"), ["中文한국어\u{20000}\u{20001}"]);
+    }
+
+    #[test]
+    fn unassigned_code_points_split_strings() {
+        let scan = |reject_unassigned| {
+            let mission = MissionConfig::new(UTF_8)
+                .chars_min(3)
+                .ascii_filter(crate::AF_ALL)
+                .unicode_block_filter(crate::UBF_ALL)
+                .reject_unassigned(reject_unassigned);
+            let scanner = StringsScanner::builder().mission(mission).build().unwrap();
+            let bytes = "\0abc\u{fdd0}def\u{ffff}ghi\u{e000}jkl\u{f0000}mno\0".as_bytes();
+            let findings = scanner.scan_bytes(bytes).unwrap().findings;
+            findings
+                .into_iter()
+                .map(|finding| finding.s)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            scan(false),
+            ["abc\u{fdd0}def\u{ffff}ghi\u{e000}jkl\u{f0000}mno"]
+        );
+        // Private use characters are assigned.
+        assert_eq!(scan(true), ["abc", "def", "ghi\u{e000}jkl\u{f0000}mno"]);
+        let unassigned = unassigned();
+        for cp in [0xfdd0, 0xfdef, 0xfffe, 0xffff, 0x1_fffe, 0x10_ffff] {
+            assert!(unassigned.pass(cp), "U+{:X}", cp);
+        }
+        for cp in [0x41, 0xe000, 0xf8ff, 0xf_0000, 0x10_fffd] {
+            assert!(!unassigned.pass(cp), "U+{:X}", cp);
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Plain text output of findings, one finding per line, like GNU `strings`.
+
+use crate::{finding::OwnedFinding, options::Radix};
+use std::io::Write;
+
+/// Width the offset column is padded to, as with `strings -t`.
+const OFFSET_WIDTH: usize = 7;
+
+/// Writes findings as lines of tab separated columns:
+///
+/// ```text
+/// [file]  [precision marker and offset]  [scanner (encoding)]  [keyword@offset]  [language]  string
+/// ```
+///
+/// Only the string column is written by default. See
+/// `StringsScanner::text_writer()` for a writer configured like the scanner.
+pub struct TextWriter<W: Write> {
+    out: W,
+    radix: Option<Radix>,
+    file_column: bool,
+    mission_column: bool,
+    keyword_column: bool,
+    language_column: bool,
+}
+
+impl<W: Write> TextWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out,
+            radix: None,
+            file_column: false,
+            mission_column: false,
+            keyword_column: false,
+            language_column: false,
+        }
+    }
+
+    /// Writes the offset of every finding in `radix`, preceded by the
+    /// precision marker, see `Precision::marker()`. `None` omits the column.
+    pub fn radix(mut self, radix: Option<Radix>) -> Self {
+        self.radix = radix;
+        self
+    }
+
+    /// Writes the path or label of the input, useful with several inputs.
+    pub fn file_column(mut self, file_column: bool) -> Self {
+        self.file_column = file_column;
+        self
+    }
+
+    /// Writes the scanner label and the encoding, e.g. `b (UTF-16LE)`,
+    /// useful with several missions.
+    pub fn mission_column(mut self, mission_column: bool) -> Self {
+        self.mission_column = mission_column;
+        self
+    }
+
+    /// Writes the matched keyword and its character offset in the string,
+    /// e.g. `mutex@12`, see `OwnedFinding::keyword_match`. The column is
+    /// empty for findings without a match.
+    pub fn keyword_column(mut self, keyword_column: bool) -> Self {
+        self.keyword_column = keyword_column;
+        self
+    }
+
+    /// Writes the detected language and its confidence, e.g. `ru 0.87`,
+    /// see `OwnedFinding::language`. The column is empty for findings
+    /// without a language.
+    pub fn language_column(mut self, language_column: bool) -> Self {
+        self.language_column = language_column;
+        self
+    }
+
+    pub fn write(&mut self, finding: &OwnedFinding) -> crate::Result<()> {
+        if self.file_column {
+            write!(self.out, "{}\t", finding.input_label)?;
+        }
+        if let Some(radix) = self.radix {
+            write!(
+                self.out,
+                "{}{:>width$}\t",
+                finding.position_precision.marker(),
+                radix.format(finding.position),
+                width = OFFSET_WIDTH
+            )?;
+        }
+        if self.mission_column {
+            write!(
+                self.out,
+                "{} ({})\t",
+                finding.mission.scanner_label(),
+                finding.encoding_name()
+            )?;
+        }
+        if self.keyword_column {
+            if let Some(keyword_match) = &finding.keyword_match {
+                write!(
+                    self.out,
+                    "{}@{}",
+                    keyword_match.keyword, keyword_match.char_offset
+                )?;
+            }
+            self.out.write_all(b"\t")?;
+        }
+        if self.language_column {
+            if let Some(guess) = finding.language {
+                write!(self.out, "{} {:.2}", guess.language, guess.confidence)?;
+            }
+            self.out.write_all(b"\t")?;
+        }
+        self.out.write_all(finding.s.as_bytes())?;
+        self.out.write_all(b"\n")?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}